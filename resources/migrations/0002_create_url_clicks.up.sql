@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS url_clicks (
+    id BIGSERIAL UNIQUE PRIMARY KEY NOT NULL,
+    key VARCHAR(128) NOT NULL,
+    clicked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    referrer TEXT,
+    user_agent TEXT,
+    ip_hash VARCHAR(64),
+    FOREIGN KEY (key) REFERENCES key_urls (key) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS url_clicks_key_clicked_at_idx ON url_clicks (key, clicked_at);
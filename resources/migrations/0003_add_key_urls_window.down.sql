@@ -0,0 +1,2 @@
+ALTER TABLE key_urls DROP COLUMN IF EXISTS expires_at;
+ALTER TABLE key_urls DROP COLUMN IF EXISTS activates_at;
@@ -0,0 +1,2 @@
+ALTER TABLE key_urls ADD COLUMN IF NOT EXISTS activates_at TIMESTAMPTZ;
+ALTER TABLE key_urls ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
@@ -0,0 +1 @@
+ALTER TABLE key_urls ADD COLUMN IF NOT EXISTS redirect_type SMALLINT CHECK (redirect_type IN (301, 302, 303, 307, 308));
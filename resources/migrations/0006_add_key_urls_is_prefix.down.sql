@@ -0,0 +1 @@
+ALTER TABLE key_urls DROP COLUMN IF EXISTS is_prefix;
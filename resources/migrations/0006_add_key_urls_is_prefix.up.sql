@@ -0,0 +1 @@
+ALTER TABLE key_urls ADD COLUMN IF NOT EXISTS is_prefix BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1 @@
+ALTER TABLE key_urls ADD COLUMN IF NOT EXISTS passphrase_hash VARCHAR(1024);
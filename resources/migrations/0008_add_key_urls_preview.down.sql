@@ -0,0 +1,2 @@
+ALTER TABLE key_urls DROP COLUMN IF EXISTS created_at;
+ALTER TABLE key_urls DROP COLUMN IF EXISTS preview_enabled;
@@ -0,0 +1,2 @@
+ALTER TABLE key_urls ADD COLUMN IF NOT EXISTS preview_enabled BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE key_urls ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS domain_rules (
+    id serial UNIQUE PRIMARY KEY NOT NULL,
+    pattern VARCHAR(253) NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (pattern, kind)
+);
@@ -0,0 +1,16 @@
+-- Reverting would turn soft-deleted rows back into live ones, so it refuses
+-- to run until they have been purged or restored
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM key_urls WHERE deleted_at IS NOT NULL)
+        OR EXISTS (SELECT 1 FROM users WHERE deleted_at IS NOT NULL) THEN
+        RAISE EXCEPTION 'Soft-deleted users or URLs exist; purge or restore them before reverting';
+    END IF;
+END
+$$;
+
+DROP INDEX IF EXISTS key_urls_deleted_at_idx;
+DROP INDEX IF EXISTS users_deleted_at_idx;
+
+ALTER TABLE key_urls DROP COLUMN IF EXISTS deleted_at;
+ALTER TABLE users DROP COLUMN IF EXISTS deleted_at;
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS key_url_collaborators (
+    key VARCHAR(128) NOT NULL,
+    user_id INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (key, user_id),
+    FOREIGN KEY (key) REFERENCES key_urls (key) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS key_url_collaborators_user_id_idx ON key_url_collaborators (user_id);
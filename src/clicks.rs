@@ -0,0 +1,178 @@
+use chrono::{DateTime, Utc};
+use rocket::{
+    fairing::AdHoc,
+    tokio::{
+        self,
+        sync::mpsc::{self, error::TrySendError, Receiver, Sender},
+    },
+};
+use rocket_sync_db_pools::postgres::{Client, Error as PostgresError, GenericClient, NoTls};
+use sha2::{Digest, Sha256};
+
+use crate::config::app::AppConfig;
+use crate::services::types::click::CreateClickRequest;
+
+/// Clicks waiting to be written before new ones are dropped.
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// Most clicks written by a single statement.
+const BATCH_SIZE: usize = 500;
+
+pub struct QueuedClick {
+    pub key: String,
+    pub clicked_at: DateTime<Utc>,
+    pub referrer: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_hash: Option<String>,
+}
+
+/// Hands clicks to a background writer so that recording one never holds up
+/// a redirect or takes a connection from the pool.
+pub struct ClickQueue {
+    sender: Sender<QueuedClick>,
+    ip_salt: String,
+}
+
+impl ClickQueue {
+    /// Queues a click to be written. A full queue drops the click rather than
+    /// slowing redirects down.
+    pub fn record(&self, click: CreateClickRequest) {
+        let queued = QueuedClick {
+            key: click.key.to_ascii_lowercase(),
+            clicked_at: Utc::now(),
+            referrer: click.visitor.referrer,
+            user_agent: click.visitor.user_agent,
+            ip_hash: click.visitor.ip.map(|ip| self.hash_ip(&ip.to_string())),
+        };
+
+        match self.sender.try_send(queued) {
+            Ok(()) => {}
+            Err(TrySendError::Full(click)) => {
+                warn!("Click queue is full, dropped a click on {}", click.key);
+            }
+            Err(TrySendError::Closed(click)) => {
+                error!("Click writer has stopped, dropped a click on {}", click.key);
+            }
+        }
+    }
+
+    fn hash_ip(&self, ip: &str) -> String {
+        let mut hasher = Sha256::new();
+
+        hasher.update(self.ip_salt.as_bytes());
+        hasher.update(ip.as_bytes());
+
+        return format!("{:x}", hasher.finalize());
+    }
+}
+
+/// Writes a batch of clicks in one statement. Clicks on keys that no longer
+/// exist are skipped instead of failing the batch.
+pub fn write_clicks(
+    client: &mut impl GenericClient,
+    clicks: &[QueuedClick],
+) -> Result<u64, PostgresError> {
+    let keys: Vec<&str> = clicks.iter().map(|click| click.key.as_str()).collect();
+    let clicked_at: Vec<DateTime<Utc>> = clicks.iter().map(|click| click.clicked_at).collect();
+    let referrers: Vec<Option<&str>> = clicks
+        .iter()
+        .map(|click| click.referrer.as_deref())
+        .collect();
+    let user_agents: Vec<Option<&str>> = clicks
+        .iter()
+        .map(|click| click.user_agent.as_deref())
+        .collect();
+    let ip_hashes: Vec<Option<&str>> = clicks
+        .iter()
+        .map(|click| click.ip_hash.as_deref())
+        .collect();
+
+    return client.execute(
+        "INSERT INTO url_clicks (key, clicked_at, referrer, user_agent, ip_hash) \
+        SELECT clicks.key, clicks.clicked_at, clicks.referrer, clicks.user_agent, clicks.ip_hash \
+        FROM UNNEST($1::VARCHAR[], $2::TIMESTAMPTZ[], $3::TEXT[], $4::TEXT[], $5::VARCHAR[]) \
+        AS clicks (key, clicked_at, referrer, user_agent, ip_hash) \
+        WHERE EXISTS (SELECT 1 FROM key_urls WHERE key_urls.key = clicks.key);",
+        &[&keys, &clicked_at, &referrers, &user_agents, &ip_hashes],
+    );
+}
+
+/// Manages the `ClickQueue` and starts its writer. The writer keeps a single
+/// connection of its own, opened on the first click and reopened once lost.
+/// Clicks still queued when the server stops are lost.
+pub fn fairing() -> AdHoc {
+    return AdHoc::try_on_ignite("Click writer", |rocket| {
+        Box::pin(async move {
+            let ip_salt = match rocket.state::<AppConfig>() {
+                Some(app_config) => app_config.click_ip_salt.clone(),
+                None => {
+                    error!("Unable to read the click IP salt");
+                    return Err(rocket);
+                }
+            };
+
+            let url = match rocket
+                .figment()
+                .extract_inner::<String>("databases.url_linker.url")
+            {
+                Ok(url) => url,
+                Err(e) => {
+                    error!(
+                        "Unable to read the database configuration to record clicks: {}",
+                        e
+                    );
+                    return Err(rocket);
+                }
+            };
+
+            let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+
+            tokio::spawn(write_queued_clicks(receiver, url));
+
+            return Ok(rocket.manage(ClickQueue { sender, ip_salt }));
+        })
+    });
+}
+
+async fn write_queued_clicks(mut receiver: Receiver<QueuedClick>, url: String) {
+    let mut client: Option<Client> = None;
+
+    while let Some(click) = receiver.recv().await {
+        let mut batch = vec![click];
+
+        while batch.len() < BATCH_SIZE {
+            match receiver.try_recv() {
+                Ok(click) => batch.push(click),
+                Err(_) => break,
+            }
+        }
+
+        let url = url.clone();
+        let open_client = client.take();
+
+        let result = tokio::task::spawn_blocking(move || {
+            if let Some(mut client) = open_client {
+                match write_clicks(&mut client, &batch) {
+                    Ok(_) => return Ok(client),
+                    // The server may have dropped the connection since the last
+                    // batch, which a new one gets past
+                    Err(e) if e.is_closed() => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            let mut client = Client::connect(&url, NoTls)?;
+
+            write_clicks(&mut client, &batch)?;
+
+            return Ok::<Client, PostgresError>(client);
+        })
+        .await;
+
+        match result {
+            Ok(Ok(open_client)) => client = Some(open_client),
+            Ok(Err(e)) => error!("Failed to record clicks: {}", e),
+            Err(e) => error!("Click writer panicked: {}", e),
+        }
+    }
+}
@@ -0,0 +1,754 @@
+use rocket::figment::{
+    providers::{Env, Format, Serialized, Toml},
+    Figment,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::services::{
+    key::{KeyGenerator, KeyGeneratorRef, BASE36_ALPHABET},
+    password::{Argon2Config, Argon2ConfigRef},
+    types::url::RedirectType,
+};
+use crate::utils;
+
+/// Settings read from a TOML file, `url-linker.toml` unless `CONFIG_FILE`
+/// names another, and overridden by environment variables. Any setting can be
+/// overridden with a `URL_LINKER_` variable, using `__` between sections,
+/// such as `URL_LINKER_ARGON2__MEMORY_COST`. The variables the server has
+/// always read, such as `HASHER_SECRET`, still take precedence.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Base URL that short links are served from, without a trailing slash
+    pub public_base_url: String,
+    pub hasher_secret: String,
+    pub click_ip_salt: String,
+    /// Apply pending migrations before the server starts
+    pub run_migrations_on_startup: bool,
+    /// Status code of redirects for URLs that don't set their own
+    pub default_redirect_type: u16,
+    pub headers: HeaderNames,
+    pub argon2: Argon2Params,
+    pub keys: KeyLimits,
+    pub generated_keys: GeneratedKeys,
+    pub client_ids: LengthLimits,
+    pub client_secrets: LengthLimits,
+    pub passphrases: LengthLimits,
+    pub redirect_cache: RedirectCacheSettings,
+    pub cors: CorsSettings,
+    pub rate_limits: RateLimitSettings,
+    pub lockout: LockoutSettings,
+    pub passphrase_lockout: PassphraseLockoutSettings,
+    pub deletion: DeletionSettings,
+}
+
+/// Request headers that carry client credentials
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HeaderNames {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Parameters new hashes are made with. Client secrets hashed with other
+/// parameters are hashed again the next time they are verified.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Argon2Params {
+    /// Memory in KiB
+    pub memory_cost: u32,
+    /// Number of passes over the memory
+    pub time_cost: u32,
+    /// Degree of parallelism
+    pub lanes: u32,
+    /// Length of the hash in bytes
+    pub hash_length: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeyLimits {
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Path segments that keys can't start with, because the server's own
+    /// routes live there
+    pub reserved_prefixes: Vec<String>,
+}
+
+/// Keys generated for URLs created without one
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GeneratedKeys {
+    /// Characters keys are made of, ignoring case
+    pub alphabet: String,
+    pub length: usize,
+    /// Keys to try before giving up when the generated ones are taken
+    pub max_attempts: usize,
+    /// Leave out characters that are easily confused, such as `0` and `o`
+    pub exclude_lookalikes: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct LengthLimits {
+    pub min_length: usize,
+    pub max_length: usize,
+}
+
+/// In-memory cache of resolved URLs, which makes redirects skip the database
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RedirectCacheSettings {
+    pub enabled: bool,
+    /// Most URLs kept at once
+    pub capacity: usize,
+    pub ttl_seconds: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CorsSettings {
+    /// Origins allowed to make credentialed cross-origin requests, such as
+    /// `https://example.com`
+    pub allowed_origins: Vec<String>,
+    /// How long browsers may cache preflight responses
+    pub max_age_seconds: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RateLimitSettings {
+    /// Authenticated API requests, by client_id
+    pub api: RateLimitGroup,
+    /// Redirects, unlock attempts and previews, by IP
+    pub visitor: RateLimitGroup,
+    /// Failed authentication attempts, by IP
+    pub auth_failures: RateLimitGroup,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct RateLimitGroup {
+    /// Requests allowed at once
+    pub burst: u32,
+    /// Requests regained per minute. 0 disables the limit.
+    pub per_minute: u32,
+}
+
+/// Lockouts of client credentials after consecutive failed verifications
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LockoutSettings {
+    /// Length of the first lockout, which doubles with each further one
+    pub base_seconds: u64,
+    pub max_seconds: u64,
+    /// Failures of one client_id before it is locked out
+    pub client_max_failures: u32,
+    /// Failures from one IP before it is locked out
+    pub ip_max_failures: u32,
+}
+
+/// Lockouts of visitors after failed passphrases on protected URLs
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PassphraseLockoutSettings {
+    pub max_failures: u32,
+    pub lockout_seconds: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DeletionSettings {
+    /// How long deleted URLs and users can be restored before they are purged
+    pub retention_days: u32,
+    /// How often to purge. 0 disables the purge job.
+    pub purge_interval_seconds: u64,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        return Self {
+            public_base_url: String::from("http://localhost:8000"),
+            hasher_secret: String::new(),
+            click_ip_salt: String::new(),
+            run_migrations_on_startup: false,
+            default_redirect_type: 303,
+            headers: HeaderNames::default(),
+            argon2: Argon2Params::default(),
+            keys: KeyLimits::default(),
+            generated_keys: GeneratedKeys::default(),
+            client_ids: LengthLimits {
+                min_length: 3,
+                max_length: 256,
+            },
+            client_secrets: LengthLimits {
+                min_length: 6,
+                max_length: 1024,
+            },
+            passphrases: LengthLimits {
+                min_length: 4,
+                max_length: 256,
+            },
+            redirect_cache: RedirectCacheSettings::default(),
+            cors: CorsSettings::default(),
+            rate_limits: RateLimitSettings::default(),
+            lockout: LockoutSettings::default(),
+            passphrase_lockout: PassphraseLockoutSettings::default(),
+            deletion: DeletionSettings::default(),
+        };
+    }
+}
+
+impl Default for Argon2Params {
+    fn default() -> Self {
+        let defaults = Argon2Config::default();
+
+        return Self {
+            memory_cost: defaults.mem_cost,
+            time_cost: defaults.time_cost,
+            lanes: defaults.lanes,
+            hash_length: defaults.hash_length,
+        };
+    }
+}
+
+impl Default for KeyLimits {
+    fn default() -> Self {
+        return Self {
+            min_length: 1,
+            max_length: 128,
+            reserved_prefixes: vec![String::from("api"), String::from("client")],
+        };
+    }
+}
+
+impl Default for GeneratedKeys {
+    fn default() -> Self {
+        return Self {
+            alphabet: String::from(BASE36_ALPHABET),
+            length: 6,
+            max_attempts: 10,
+            exclude_lookalikes: false,
+        };
+    }
+}
+
+impl Default for RedirectCacheSettings {
+    fn default() -> Self {
+        return Self {
+            enabled: false,
+            capacity: 10_000,
+            ttl_seconds: 60,
+        };
+    }
+}
+
+impl Default for CorsSettings {
+    fn default() -> Self {
+        return Self {
+            allowed_origins: vec![],
+            max_age_seconds: 86_400,
+        };
+    }
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        return Self {
+            api: RateLimitGroup {
+                burst: 120,
+                per_minute: 600,
+            },
+            visitor: RateLimitGroup {
+                burst: 60,
+                per_minute: 300,
+            },
+            auth_failures: RateLimitGroup {
+                burst: 10,
+                per_minute: 5,
+            },
+        };
+    }
+}
+
+impl Default for LockoutSettings {
+    fn default() -> Self {
+        return Self {
+            base_seconds: 60,
+            max_seconds: 86_400,
+            client_max_failures: 5,
+            ip_max_failures: 20,
+        };
+    }
+}
+
+impl Default for PassphraseLockoutSettings {
+    fn default() -> Self {
+        return Self {
+            max_failures: 5,
+            lockout_seconds: 900,
+        };
+    }
+}
+
+impl Default for DeletionSettings {
+    fn default() -> Self {
+        return Self {
+            retention_days: 30,
+            purge_interval_seconds: 3600,
+        };
+    }
+}
+
+/// Longest key the `key_urls.key` column holds
+const MAX_KEY_LENGTH: usize = 128;
+
+/// Longest client_id the `users.client_id` column holds
+const MAX_CLIENT_ID_LENGTH: usize = 256;
+
+/// Prefixes the server's routes need, which can't be configured away
+const REQUIRED_RESERVED_PREFIXES: [&str; 2] = ["api", "client"];
+
+impl AppConfig {
+    fn apply_env_overrides(&mut self) -> Result<(), String> {
+        override_from_env("PUBLIC_BASE_URL", &mut self.public_base_url)?;
+        override_from_env("HASHER_SECRET", &mut self.hasher_secret)?;
+        override_from_env("CLICK_IP_SALT", &mut self.click_ip_salt)?;
+        override_from_env("HEADER_CLIENT_ID", &mut self.headers.client_id)?;
+        override_from_env("HEADER_CLIENT_SECRET", &mut self.headers.client_secret)?;
+
+        let generated_keys = &mut self.generated_keys;
+        override_from_env("GENERATED_KEY_ALPHABET", &mut generated_keys.alphabet)?;
+        override_from_env("GENERATED_KEY_LENGTH", &mut generated_keys.length)?;
+        override_from_env(
+            "GENERATED_KEY_MAX_ATTEMPTS",
+            &mut generated_keys.max_attempts,
+        )?;
+        override_from_env(
+            "GENERATED_KEY_EXCLUDE_LOOKALIKES",
+            &mut generated_keys.exclude_lookalikes,
+        )?;
+
+        override_from_env(
+            "RUN_MIGRATIONS_ON_STARTUP",
+            &mut self.run_migrations_on_startup,
+        )?;
+        override_from_env("DEFAULT_REDIRECT_TYPE", &mut self.default_redirect_type)?;
+
+        let redirect_cache = &mut self.redirect_cache;
+        override_from_env("REDIRECT_CACHE_ENABLED", &mut redirect_cache.enabled)?;
+        override_from_env("REDIRECT_CACHE_CAPACITY", &mut redirect_cache.capacity)?;
+        override_from_env(
+            "REDIRECT_CACHE_TTL_SECONDS",
+            &mut redirect_cache.ttl_seconds,
+        )?;
+
+        if let Some(value) = utils::optional_env_var("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = value
+                .split(',')
+                .map(|origin| String::from(origin.trim()))
+                .filter(|origin| !origin.is_empty())
+                .collect();
+        }
+
+        override_from_env("CORS_MAX_AGE_SECONDS", &mut self.cors.max_age_seconds)?;
+
+        for (group, settings) in [
+            ("API", &mut self.rate_limits.api),
+            ("VISITOR", &mut self.rate_limits.visitor),
+            ("AUTH_FAILURES", &mut self.rate_limits.auth_failures),
+        ] {
+            override_from_env(&format!("RATE_LIMIT_{group}_BURST"), &mut settings.burst)?;
+            override_from_env(
+                &format!("RATE_LIMIT_{group}_PER_MINUTE"),
+                &mut settings.per_minute,
+            )?;
+        }
+
+        let lockout = &mut self.lockout;
+        override_from_env("LOCKOUT_BASE_SECONDS", &mut lockout.base_seconds)?;
+        override_from_env("LOCKOUT_MAX_SECONDS", &mut lockout.max_seconds)?;
+        override_from_env(
+            "LOCKOUT_CLIENT_MAX_FAILURES",
+            &mut lockout.client_max_failures,
+        )?;
+        override_from_env("LOCKOUT_IP_MAX_FAILURES", &mut lockout.ip_max_failures)?;
+
+        let passphrase_lockout = &mut self.passphrase_lockout;
+        override_from_env(
+            "PASSPHRASE_MAX_FAILURES",
+            &mut passphrase_lockout.max_failures,
+        )?;
+        override_from_env(
+            "PASSPHRASE_LOCKOUT_SECONDS",
+            &mut passphrase_lockout.lockout_seconds,
+        )?;
+
+        let deletion = &mut self.deletion;
+        override_from_env("DELETED_RETENTION_DAYS", &mut deletion.retention_days)?;
+        override_from_env(
+            "PURGE_INTERVAL_SECONDS",
+            &mut deletion.purge_interval_seconds,
+        )?;
+
+        return Ok(());
+    }
+
+    fn validate(&mut self) -> Result<(), String> {
+        let public_base_url = self.public_base_url.trim_end_matches('/');
+
+        url::Url::parse(public_base_url)
+            .map_err(|_| String::from("public_base_url must be a valid URL"))?;
+
+        self.public_base_url = String::from(public_base_url);
+
+        for (name, value) in [
+            ("hasher_secret", &self.hasher_secret),
+            ("click_ip_salt", &self.click_ip_salt),
+        ] {
+            if value.is_empty() {
+                return Err(format!("{name} is required"));
+            }
+        }
+
+        for (name, value) in [
+            ("headers.client_id", &self.headers.client_id),
+            ("headers.client_secret", &self.headers.client_secret),
+        ] {
+            if value.is_empty() {
+                return Err(format!("{name} is required"));
+            }
+
+            if !value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(format!("{name} must be a valid header name"));
+            }
+        }
+
+        if self
+            .headers
+            .client_id
+            .eq_ignore_ascii_case(&self.headers.client_secret)
+        {
+            return Err(String::from(
+                "headers.client_id and headers.client_secret must differ",
+            ));
+        }
+
+        if self.argon2.lanes < 1 || self.argon2.lanes > 0xFF_FFFF {
+            return Err(String::from(
+                "argon2.lanes must be at least 1 and at most 16777215",
+            ));
+        }
+
+        if self.argon2.memory_cost < 8 * self.argon2.lanes {
+            return Err(String::from(
+                "argon2.memory_cost must be at least 8 KiB per lane",
+            ));
+        }
+
+        if self.argon2.time_cost < 1 {
+            return Err(String::from("argon2.time_cost must be at least 1"));
+        }
+
+        if self.argon2.hash_length < 4 {
+            return Err(String::from("argon2.hash_length must be at least 4"));
+        }
+
+        for (name, limits) in [
+            (
+                "keys",
+                LengthLimits {
+                    min_length: self.keys.min_length,
+                    max_length: self.keys.max_length,
+                },
+            ),
+            ("client_ids", self.client_ids),
+            ("client_secrets", self.client_secrets),
+            ("passphrases", self.passphrases),
+        ] {
+            if limits.min_length < 1 || limits.min_length > limits.max_length {
+                return Err(format!(
+                    "{name}.min_length must be at least 1 and at most {name}.max_length"
+                ));
+            }
+        }
+
+        if self.keys.max_length > MAX_KEY_LENGTH {
+            return Err(format!("keys.max_length must be at most {MAX_KEY_LENGTH}"));
+        }
+
+        if self.client_ids.max_length > MAX_CLIENT_ID_LENGTH {
+            return Err(format!(
+                "client_ids.max_length must be at most {MAX_CLIENT_ID_LENGTH}"
+            ));
+        }
+
+        let generated_keys = &self.generated_keys;
+
+        self.build_key_generator()
+            .map_err(|e| format!("generated_keys: {e}"))?;
+
+        if generated_keys.length < self.keys.min_length
+            || generated_keys.length > self.keys.max_length
+        {
+            return Err(String::from(
+                "generated_keys.length must be within keys.min_length and keys.max_length",
+            ));
+        }
+
+        for prefix in &mut self.keys.reserved_prefixes {
+            *prefix = prefix.trim_matches('/').to_ascii_lowercase();
+
+            if prefix.is_empty() {
+                return Err(String::from("keys.reserved_prefixes can't be empty"));
+            }
+        }
+
+        for required in REQUIRED_RESERVED_PREFIXES {
+            if !self
+                .keys
+                .reserved_prefixes
+                .iter()
+                .any(|prefix| prefix == required)
+            {
+                return Err(format!(
+                    "keys.reserved_prefixes must include \"{required}\""
+                ));
+            }
+        }
+
+        if RedirectType::from_code(self.default_redirect_type).is_none() {
+            return Err(String::from(
+                "default_redirect_type must be one of 301, 302, 303, 307 or 308",
+            ));
+        }
+
+        if self.redirect_cache.capacity < 1 {
+            return Err(String::from("redirect_cache.capacity must be at least 1"));
+        }
+
+        for origin in &mut self.cors.allowed_origins {
+            let parsed = url::Url::parse(origin)
+                .ok()
+                .map(|url| url.origin())
+                .filter(|origin| origin.is_tuple())
+                .ok_or_else(|| format!("cors.allowed_origins has an invalid origin: {origin}"))?;
+
+            *origin = parsed.ascii_serialization();
+        }
+
+        for (name, group) in [
+            ("api", self.rate_limits.api),
+            ("visitor", self.rate_limits.visitor),
+            ("auth_failures", self.rate_limits.auth_failures),
+        ] {
+            if group.burst < 1 {
+                return Err(format!("rate_limits.{name}.burst must be at least 1"));
+            }
+        }
+
+        if self.lockout.base_seconds < 1 || self.lockout.base_seconds > self.lockout.max_seconds {
+            return Err(String::from(
+                "lockout.base_seconds must be at least 1 and at most lockout.max_seconds",
+            ));
+        }
+
+        for (name, max_failures) in [
+            (
+                "lockout.client_max_failures",
+                self.lockout.client_max_failures,
+            ),
+            ("lockout.ip_max_failures", self.lockout.ip_max_failures),
+            (
+                "passphrase_lockout.max_failures",
+                self.passphrase_lockout.max_failures,
+            ),
+        ] {
+            if max_failures < 1 {
+                return Err(format!("{name} must be at least 1"));
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn build_key_generator(&self) -> Result<KeyGenerator, String> {
+        return KeyGenerator::new(
+            &self.generated_keys.alphabet,
+            self.generated_keys.length,
+            self.generated_keys.max_attempts,
+            self.generated_keys.exclude_lookalikes,
+        );
+    }
+}
+
+/// Replaces a setting with the environment variable of that name, if it's set.
+fn override_from_env<T: std::str::FromStr>(name: &str, setting: &mut T) -> Result<(), String> {
+    if let Some(value) = utils::optional_env_var(name) {
+        *setting = value
+            .parse()
+            .map_err(|_| format!("{name} has an invalid value"))?;
+    }
+
+    return Ok(());
+}
+
+/// Loads and validates the configuration, panicking on invalid settings so
+/// that the server doesn't start with them.
+pub fn build_app_config() -> AppConfig {
+    let path =
+        utils::optional_env_var("CONFIG_FILE").unwrap_or_else(|| String::from("url-linker.toml"));
+
+    // Starting from the defaults lets a file or variable set part of a section
+    let mut config: AppConfig = Figment::from(Serialized::defaults(AppConfig::default()))
+        .merge(Toml::file(path))
+        .merge(Env::prefixed("URL_LINKER_").split("__"))
+        .extract()
+        .unwrap_or_else(|e| panic!("Invalid configuration: {}", e));
+
+    if let Err(e) = config.apply_env_overrides().and_then(|_| config.validate()) {
+        panic!("Invalid configuration: {}", e);
+    }
+
+    return config;
+}
+
+pub fn build_key_generator_ref(config: &AppConfig) -> KeyGeneratorRef {
+    // Validated along with the rest of the configuration
+    let key_generator = config.build_key_generator().unwrap();
+
+    return KeyGeneratorRef::new(key_generator);
+}
+
+pub fn build_argon2_config_ref(config: &AppConfig) -> Argon2ConfigRef {
+    // The hashing config lives as long as the server, so it can borrow the
+    // secret for good
+    let secret: &'static str = Box::leak(config.hasher_secret.clone().into_boxed_str());
+
+    return Argon2ConfigRef::new(Argon2Config {
+        secret: secret.as_bytes(),
+        variant: argon2::Variant::Argon2id,
+        mem_cost: config.argon2.memory_cost,
+        time_cost: config.argon2.time_cost,
+        lanes: config.argon2.lanes,
+        hash_length: config.argon2.hash_length,
+        ..Argon2Config::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AppConfig, MAX_CLIENT_ID_LENGTH, MAX_KEY_LENGTH};
+
+    fn valid_config() -> AppConfig {
+        let mut config = AppConfig::default();
+
+        config.hasher_secret = String::from("secret");
+        config.click_ip_salt = String::from("salt");
+        config.headers.client_id = String::from("x-client-id");
+        config.headers.client_secret = String::from("x-client-secret");
+
+        return config;
+    }
+
+    #[test]
+    fn accepts_the_defaults() {
+        assert_eq!(valid_config().validate(), Ok(()));
+    }
+
+    #[test]
+    fn keys_fit_the_key_columns() {
+        let mut config = valid_config();
+        config.keys.max_length = MAX_KEY_LENGTH;
+        assert_eq!(config.validate(), Ok(()));
+
+        let mut config = valid_config();
+        config.keys.max_length = MAX_KEY_LENGTH + 1;
+        assert_eq!(
+            config.validate(),
+            Err(format!("keys.max_length must be at most {MAX_KEY_LENGTH}"))
+        );
+    }
+
+    #[test]
+    fn client_ids_fit_the_client_id_column() {
+        let mut config = valid_config();
+        config.client_ids.max_length = MAX_CLIENT_ID_LENGTH;
+        assert_eq!(config.validate(), Ok(()));
+
+        let mut config = valid_config();
+        config.client_ids.max_length = MAX_CLIENT_ID_LENGTH + 1;
+        assert_eq!(
+            config.validate(),
+            Err(format!(
+                "client_ids.max_length must be at most {MAX_CLIENT_ID_LENGTH}"
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_inverted_length_limits() {
+        let mut config = valid_config();
+        config.passphrases.min_length = 10;
+        config.passphrases.max_length = 9;
+
+        assert!(config.validate().is_err());
+
+        let mut config = valid_config();
+        config.client_secrets.min_length = 0;
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn generated_keys_fit_the_key_limits() {
+        let mut config = valid_config();
+        config.keys.max_length = 5;
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn normalizes_reserved_prefixes_and_requires_the_routes() {
+        let mut config = valid_config();
+        config.keys.reserved_prefixes = vec![
+            String::from("/API/"),
+            String::from("Client"),
+            String::from("Docs"),
+        ];
+
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(config.keys.reserved_prefixes, ["api", "client", "docs"]);
+
+        let mut config = valid_config();
+        config.keys.reserved_prefixes = vec![String::from("api")];
+
+        assert_eq!(
+            config.validate(),
+            Err(String::from(
+                "keys.reserved_prefixes must include \"client\""
+            ))
+        );
+    }
+
+    #[test]
+    fn normalizes_cors_origins() {
+        let mut config = valid_config();
+        config.cors.allowed_origins = vec![
+            String::from("HTTPS://Example.com:443/path"),
+            String::from("http://localhost:3000"),
+        ];
+
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(
+            config.cors.allowed_origins,
+            ["https://example.com", "http://localhost:3000"]
+        );
+
+        let mut config = valid_config();
+        config.cors.allowed_origins = vec![String::from("null")];
+
+        assert!(config.validate().is_err());
+    }
+}
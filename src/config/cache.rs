@@ -0,0 +1,22 @@
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use crate::services::cache::{RedirectCache, RedirectCacheRef};
+
+use super::app::AppConfig;
+
+pub fn build_redirect_cache_ref(config: &AppConfig) -> Option<RedirectCacheRef> {
+    let settings = &config.redirect_cache;
+
+    if !settings.enabled {
+        return None;
+    }
+
+    // Validated along with the rest of the configuration
+    let capacity = NonZeroUsize::new(settings.capacity).unwrap();
+
+    return Some(RedirectCacheRef::new(RedirectCache::new(
+        capacity,
+        Duration::from_secs(settings.ttl_seconds),
+    )));
+}
@@ -0,0 +1,75 @@
+use super::app::AppConfig;
+
+/// Origins allowed to make credentialed cross-origin requests, and how
+/// browsers may cache preflight responses.
+pub struct CorsPolicy {
+    /// Serialized origins, such as `https://example.com`
+    pub allowed_origins: Vec<String>,
+    /// Request headers allowed on cross-origin requests
+    pub allowed_headers: Vec<String>,
+    pub max_age_seconds: u64,
+}
+
+impl CorsPolicy {
+    pub fn allows(&self, origin: &str) -> bool {
+        return self.allowed_origins.iter().any(|allowed| allowed == origin);
+    }
+}
+
+pub fn build_cors_policy(config: &AppConfig) -> CorsPolicy {
+    return CorsPolicy {
+        allowed_origins: config.cors.allowed_origins.clone(),
+        allowed_headers: vec![
+            String::from("Authorization"),
+            String::from("Content-Type"),
+            config.headers.client_id.clone(),
+            config.headers.client_secret.clone(),
+        ],
+        max_age_seconds: config.cors.max_age_seconds,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CorsPolicy;
+
+    fn policy(allowed_origins: &[&str]) -> CorsPolicy {
+        return CorsPolicy {
+            allowed_origins: allowed_origins
+                .iter()
+                .map(|origin| String::from(*origin))
+                .collect(),
+            allowed_headers: vec![],
+            max_age_seconds: 0,
+        };
+    }
+
+    #[test]
+    fn allows_listed_origins() {
+        let policy = policy(&["https://example.com", "http://localhost:3000"]);
+
+        assert!(policy.allows("https://example.com"));
+        assert!(policy.allows("http://localhost:3000"));
+    }
+
+    #[test]
+    fn rejects_origins_that_only_resemble_listed_ones() {
+        let policy = policy(&["https://example.com"]);
+
+        assert!(!policy.allows("http://example.com"));
+        assert!(!policy.allows("https://example.com:8443"));
+        assert!(!policy.allows("https://sub.example.com"));
+        assert!(!policy.allows("https://example.com.evil.com"));
+        assert!(!policy.allows("https://example.com/"));
+        assert!(!policy.allows("null"));
+        assert!(!policy.allows(""));
+    }
+
+    #[test]
+    fn allows_nothing_by_default() {
+        let policy = policy(&[]);
+
+        assert!(!policy.allows("https://example.com"));
+        assert!(!policy.allows("null"));
+    }
+}
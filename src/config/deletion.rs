@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use super::app::AppConfig;
+
+/// How long deleted URLs and users can be restored, with their keys kept
+/// reserved, before they are purged.
+pub struct DeletionRetention(pub chrono::Duration);
+
+/// How often deleted URLs and users past retention are purged. `None` when
+/// the purge job is disabled.
+pub struct PurgeInterval(pub Option<Duration>);
+
+pub fn build_deletion_retention(config: &AppConfig) -> DeletionRetention {
+    return DeletionRetention(chrono::Duration::days(
+        config.deletion.retention_days.into(),
+    ));
+}
+
+pub fn build_purge_interval(config: &AppConfig) -> PurgeInterval {
+    let seconds = config.deletion.purge_interval_seconds;
+
+    if seconds == 0 {
+        return PurgeInterval(None);
+    }
+
+    return PurgeInterval(Some(Duration::from_secs(seconds)));
+}
@@ -0,0 +1,32 @@
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use crate::services::lockout::{LockoutTracker, LockoutTrackerRef};
+
+use super::app::AppConfig;
+
+/// Locks out client credentials after repeated failed verifications, both by
+/// client_id and by the IP the attempts come from.
+#[derive(Clone)]
+pub struct CredentialLockouts {
+    pub clients: LockoutTrackerRef,
+    pub ips: LockoutTrackerRef,
+}
+
+pub fn build_credential_lockouts(config: &AppConfig) -> CredentialLockouts {
+    let settings = &config.lockout;
+
+    let build_tracker = |max_failures| {
+        return LockoutTrackerRef::new(LockoutTracker::new(
+            NonZeroUsize::new(10_000).unwrap(),
+            max_failures,
+            Duration::from_secs(settings.base_seconds),
+            Duration::from_secs(settings.max_seconds),
+        ));
+    };
+
+    return CredentialLockouts {
+        clients: build_tracker(settings.client_max_failures),
+        ips: build_tracker(settings.ip_max_failures),
+    };
+}
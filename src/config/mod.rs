@@ -1,2 +1,10 @@
+pub mod app;
+pub mod cache;
+pub mod cors;
 pub mod database;
+pub mod deletion;
 pub mod environment;
+pub mod lockout;
+pub mod rate_limit;
+pub mod redirect;
+pub mod unlock;
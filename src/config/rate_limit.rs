@@ -0,0 +1,35 @@
+use std::num::NonZeroUsize;
+
+use crate::services::rate_limit::{RateLimiter, RateLimiterRef};
+
+use super::app::{AppConfig, RateLimitGroup};
+
+/// Rate limits by route group. A group is `None` when its limit is disabled.
+pub struct RateLimits {
+    /// Authenticated API requests, by client_id
+    pub api: Option<RateLimiterRef>,
+    /// Redirects, unlock attempts and previews, by IP
+    pub visitor: Option<RateLimiterRef>,
+    /// Failed authentication attempts, by IP
+    pub auth_failures: Option<RateLimiterRef>,
+}
+
+pub fn build_rate_limits(config: &AppConfig) -> RateLimits {
+    return RateLimits {
+        api: build_rate_limiter(config.rate_limits.api),
+        visitor: build_rate_limiter(config.rate_limits.visitor),
+        auth_failures: build_rate_limiter(config.rate_limits.auth_failures),
+    };
+}
+
+fn build_rate_limiter(group: RateLimitGroup) -> Option<RateLimiterRef> {
+    if group.per_minute == 0 {
+        return None;
+    }
+
+    return Some(RateLimiterRef::new(RateLimiter::new(
+        NonZeroUsize::new(10_000).unwrap(),
+        group.burst,
+        group.per_minute,
+    )));
+}
@@ -0,0 +1,13 @@
+use crate::services::types::url::RedirectType;
+
+use super::app::AppConfig;
+
+/// Redirect type of URLs that don't set their own.
+pub struct DefaultRedirectType(pub RedirectType);
+
+pub fn build_default_redirect_type(config: &AppConfig) -> DefaultRedirectType {
+    // Validated along with the rest of the configuration
+    let redirect_type = RedirectType::from_code(config.default_redirect_type).unwrap();
+
+    return DefaultRedirectType(redirect_type);
+}
@@ -0,0 +1,22 @@
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use crate::services::lockout::{LockoutTracker, LockoutTrackerRef};
+
+use super::app::AppConfig;
+
+/// Limits failed passphrase attempts on protected URLs. Every lockout lasts
+/// just as long.
+pub struct UnlockLimiter(pub LockoutTrackerRef);
+
+pub fn build_unlock_limiter(config: &AppConfig) -> UnlockLimiter {
+    let settings = &config.passphrase_lockout;
+    let lockout = Duration::from_secs(settings.lockout_seconds);
+
+    return UnlockLimiter(LockoutTrackerRef::new(LockoutTracker::new(
+        NonZeroUsize::new(10_000).unwrap(),
+        settings.max_failures,
+        lockout,
+        lockout,
+    )));
+}
@@ -0,0 +1,21 @@
+use rocket::{routes, Build, Rocket};
+
+use crate::errors::audit::AuditError;
+use crate::services::{audit::AuditService, types::admin::Admin};
+
+use super::super::types::{request::audit::AuditListQuery, response::audit::AuditEntries};
+
+pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
+    return rocket.mount("/api/v1/audit", routes![get_all]);
+}
+
+#[get("/?<query..>")]
+async fn get_all(
+    _admin: Admin,
+    audit_service: Box<dyn AuditService>,
+    query: AuditListQuery,
+) -> Result<AuditEntries, AuditError> {
+    let entries = audit_service.get_all(query.into()).await?;
+
+    return Ok(AuditEntries::from(entries));
+}
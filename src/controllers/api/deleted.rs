@@ -0,0 +1,63 @@
+use rocket::{routes, Build, Rocket};
+
+use crate::errors::{url::UrlError, user::UserError};
+use crate::services::{types::admin::Admin, url::UrlService, user::UserService};
+
+use super::super::types::{
+    request::{page::PageQuery, url::UrlRestoreKey},
+    response::{
+        url::{Url, Urls},
+        user::{DeletedUsers, RestoredUser},
+    },
+};
+
+pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
+    return rocket.mount(
+        "/api/v1/deleted",
+        routes![get_all_urls, restore_url, get_all_users, restore_user],
+    );
+}
+
+#[get("/urls?<query..>")]
+async fn get_all_urls(
+    _admin: Admin,
+    url_service: Box<dyn UrlService>,
+    query: PageQuery,
+) -> Result<Urls, UrlError> {
+    let urls = url_service.get_all_deleted(query.into()).await?;
+
+    return Ok(Urls::from(urls));
+}
+
+#[post("/urls/<key..>")]
+async fn restore_url(
+    admin: Admin,
+    url_service: Box<dyn UrlService>,
+    key: UrlRestoreKey,
+) -> Result<Url, UrlError> {
+    let url = url_service.restore_by_key(admin.0, key.0).await?;
+
+    return Ok(Url::from(url));
+}
+
+#[get("/users?<query..>")]
+async fn get_all_users(
+    _admin: Admin,
+    user_service: Box<dyn UserService>,
+    query: PageQuery,
+) -> Result<DeletedUsers, UserError> {
+    let users = user_service.get_all_deleted(query.into()).await?;
+
+    return Ok(DeletedUsers::from(users));
+}
+
+#[post("/users/<id>/restore")]
+async fn restore_user(
+    admin: Admin,
+    user_service: Box<dyn UserService>,
+    id: i32,
+) -> Result<RestoredUser, UserError> {
+    let restored = user_service.restore_by_id(admin.0, id).await?;
+
+    return Ok(RestoredUser::from(restored));
+}
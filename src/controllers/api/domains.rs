@@ -0,0 +1,48 @@
+use rocket::{routes, serde::json::Json, Build, Rocket};
+
+use crate::errors::domain::DomainRuleError;
+use crate::services::{domain::DomainRuleService, types::admin::Admin};
+
+use super::super::types::{
+    request::domain::{CreateDomainRule, DomainRuleKind},
+    response::domain::{DomainRule, DomainRules},
+};
+
+pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
+    return rocket.mount("/api/v1/domains", routes![create, get_all, delete_by_id]);
+}
+
+#[post("/", data = "<rule>")]
+async fn create(
+    _admin: Admin,
+    domain_service: Box<dyn DomainRuleService>,
+    rule: Json<CreateDomainRule>,
+) -> Result<DomainRule, DomainRuleError> {
+    let rule: CreateDomainRule = rule.0;
+
+    let rule = domain_service.create(rule.into()).await?;
+
+    return Ok(DomainRule::from(rule));
+}
+
+#[get("/?<kind>")]
+async fn get_all(
+    _admin: Admin,
+    domain_service: Box<dyn DomainRuleService>,
+    kind: Option<DomainRuleKind>,
+) -> Result<DomainRules, DomainRuleError> {
+    let rules = domain_service.get_all(kind.map(Into::into)).await?;
+
+    return Ok(DomainRules::from(rules));
+}
+
+#[delete("/<id>")]
+async fn delete_by_id(
+    _admin: Admin,
+    domain_service: Box<dyn DomainRuleService>,
+    id: i32,
+) -> Result<(), DomainRuleError> {
+    domain_service.delete_by_id(id).await?;
+
+    return Ok(());
+}
@@ -1,9 +1,17 @@
 use rocket::{
     fs::{relative, FileServer},
     http::Status,
-    routes, Build, Rocket,
+    routes, Build, Rocket, State,
 };
 
+use crate::services::cache::RedirectCacheRef;
+
+use super::types::response::health::{Health, RedirectCacheStats};
+
+mod audit;
+mod deleted;
+mod domains;
+pub mod preview;
 mod urls;
 mod users;
 
@@ -15,6 +23,10 @@ pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
         FileServer::from(relative!("resources/swagger")).rank(1),
     );
 
+    let rocket = audit::mount(rocket);
+    let rocket = deleted::mount(rocket);
+    let rocket = domains::mount(rocket);
+    let rocket = preview::mount(rocket);
     let rocket = urls::mount(rocket);
     let rocket = users::mount(rocket);
 
@@ -27,6 +39,13 @@ async fn index() -> Status {
 }
 
 #[get("/health")]
-async fn health() -> &'static str {
-    return "Service is healthy.";
+async fn health(redirect_cache: &State<Option<RedirectCacheRef>>) -> Health {
+    let redirect_cache = redirect_cache
+        .as_ref()
+        .map(|redirect_cache| RedirectCacheStats::from(redirect_cache.stats()));
+
+    return Health {
+        status: String::from("Service is healthy."),
+        redirect_cache,
+    };
 }
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use rocket::{routes, Build, Rocket};
+
+use crate::errors::url::UrlError;
+use crate::services::{
+    click::ClickService,
+    types::{rate_limit::VisitorRateLimit, url::UrlWindowState as ServiceUrlWindowState},
+    url::UrlService,
+    user::UserService,
+};
+
+use super::super::types::response::{preview::UrlPreview, url::UrlWindowState};
+
+pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
+    return rocket.mount("/api/v1/preview", routes![get_by_key]);
+}
+
+#[get("/<key..>")]
+async fn get_by_key(
+    _rate_limit: VisitorRateLimit,
+    url_service: Box<dyn UrlService>,
+    user_service: Box<dyn UserService>,
+    click_service: Box<dyn ClickService>,
+    key: PathBuf,
+) -> Result<UrlPreview, UrlError> {
+    let key = key.display().to_string();
+
+    return build_preview(url_service, user_service, click_service, key).await;
+}
+
+/// Describes where a URL leads without redirecting, including expired URLs.
+/// URLs that aren't active yet are not found, as they are when redirecting.
+/// URLs whose owner turned previews off, and passphrase-protected URLs, can't
+/// be previewed.
+pub async fn build_preview(
+    url_service: Box<dyn UrlService>,
+    user_service: Box<dyn UserService>,
+    click_service: Box<dyn ClickService>,
+    key: String,
+) -> Result<UrlPreview, UrlError> {
+    let url = url_service.get_by_key_in_any_window(key).await?;
+
+    // Like the redirect, don't give away links that aren't live yet
+    if let ServiceUrlWindowState::Pending = url.window_state() {
+        return Err(UrlError::NotFound);
+    }
+
+    if !url.preview_enabled || url.passphrase_hash.is_some() {
+        return Err(UrlError::PreviewDisabled);
+    }
+
+    let owner = user_service.get_by_id(url.user_id).await.map_err(|e| {
+        error!("Failed to get the owner of {}: {:?}", url.key, e);
+        return UrlError::Unknown;
+    })?;
+
+    let clicks = click_service.count_by_key(url.key.clone()).await?;
+    let window_state = UrlWindowState::from(url.window_state());
+
+    return Ok(UrlPreview {
+        key: url.key,
+        url: url.url,
+        owner: owner.client_id,
+        created_at: url.created_at,
+        clicks,
+        window_state,
+    });
+}
@@ -1,14 +1,32 @@
 use std::path::PathBuf;
 
-use rocket::{routes, serde::json::Json, Build, Rocket};
+use rocket::{
+    http::ContentType, response::stream::TextStream, routes, serde::json::Json, Build, Rocket,
+    State,
+};
+
+use crate::config::app::AppConfig;
 
 use crate::errors::url::UrlError;
-use crate::services::types::admin::Admin;
-use crate::services::{types::user::User, url::UrlService};
+use crate::services::types::{
+    admin::Admin,
+    auth::{UrlsReader, UrlsWriter},
+    page::{Page, PageRequest, MAX_LIMIT},
+    url::{Url as ServiceUrl, UrlQuery},
+};
+use crate::services::{click::ClickService, qr, url::UrlService};
 
 use super::super::types::{
-    request::url::{CreateUrl, UpdateUrl},
-    response::url::{Url, Urls},
+    request::url::{
+        AddUrlCollaborator, CreateUrl, ExportFormat, ImportMode, QrQuery, UpdateUrl,
+        UrlCollaboratorPath, UrlCollaboratorsKey, UrlImport, UrlListQuery, UrlQrKey, UrlStatsKey,
+        UrlTransfer, UrlTransferKey,
+    },
+    response::{
+        click::ClickStats,
+        qr::QrCode,
+        url::{ExportUrl, ImportReport, Url, UrlCollaborators, Urls},
+    },
 };
 
 pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
@@ -16,10 +34,18 @@ pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
         "/api/v1/urls",
         routes![
             create,
+            import,
+            export,
             get_all_for_admin,
             get_all_by_user_id,
+            get_stats_by_key,
+            get_qr_by_key,
+            get_collaborators,
             get_by_key,
+            add_collaborator,
+            transfer_by_key,
             update_by_key,
+            remove_collaborator,
             delete_by_key
         ],
     );
@@ -27,10 +53,11 @@ pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
 
 #[post("/", data = "<url>")]
 async fn create(
-    user: User,
+    writer: UrlsWriter,
     url_service: Box<dyn UrlService>,
     url: Json<CreateUrl>,
 ) -> Result<Url, UrlError> {
+    let user = writer.0;
     let url: CreateUrl = url.0;
 
     let url = url_service.create(user, url.into()).await?;
@@ -38,42 +65,220 @@ async fn create(
     return Ok(Url::from(url));
 }
 
-#[get("/?include_all", rank = 1)]
+#[post("/import?<mode>", data = "<urls>")]
+async fn import(
+    writer: UrlsWriter,
+    url_service: Box<dyn UrlService>,
+    mode: Option<ImportMode>,
+    urls: Result<UrlImport, UrlError>,
+) -> Result<ImportReport, UrlError> {
+    let user = writer.0;
+    let urls = urls?
+        .0
+        .into_iter()
+        .map(|url| url.map(|url| url.into()))
+        .collect();
+    let mode = mode.unwrap_or(ImportMode::AllOrNothing);
+
+    let report = url_service.import(user, urls, mode.into()).await?;
+
+    return Ok(ImportReport::from(report));
+}
+
+/// Pages by key rather than offset, so that URLs created or deleted during an
+/// export don't shift the pages after them.
+async fn get_export_page(
+    url_service: &dyn UrlService,
+    user_id: Option<i32>,
+    after_key: Option<String>,
+) -> Result<Page<ServiceUrl>, UrlError> {
+    return url_service
+        .get_all(UrlQuery {
+            page: PageRequest {
+                limit: Some(MAX_LIMIT),
+                cursor: None,
+            },
+            user_id,
+            after_key,
+            ..Default::default()
+        })
+        .await;
+}
+
+/// Streams the caller's URLs, or every URL for admins, one page at a time.
+#[get("/export?<format>")]
+async fn export(
+    reader: UrlsReader,
+    url_service: Box<dyn UrlService>,
+    format: Option<ExportFormat>,
+) -> Result<(ContentType, TextStream![String]), UrlError> {
+    let user = reader.0;
+    let format = format.unwrap_or(ExportFormat::Json);
+    let user_id = if user.is_admin { None } else { Some(user.id) };
+
+    // Fetch the first page up front so that errors still produce a status
+    let first_page = get_export_page(url_service.as_ref(), user_id, None).await?;
+
+    let content_type = match format {
+        ExportFormat::Csv => ContentType::CSV,
+        ExportFormat::Json => ContentType::JSON,
+    };
+
+    let stream = TextStream! {
+        yield String::from(match format {
+            ExportFormat::Csv => ExportUrl::CSV_HEADER,
+            ExportFormat::Json => "[",
+        });
+
+        let mut page = first_page;
+        let mut is_first = true;
+
+        loop {
+            let after_key = match page.values.last() {
+                Some(url) if page.next.is_some() => Some(url.key.clone()),
+                _ => None,
+            };
+
+            for url in page.values {
+                let url = ExportUrl::from(url);
+
+                yield match format {
+                    ExportFormat::Csv => url.to_csv(),
+                    ExportFormat::Json if is_first => url.to_json(),
+                    ExportFormat::Json => format!(",{}", url.to_json()),
+                };
+
+                is_first = false;
+            }
+
+            let after_key = match after_key {
+                Some(after_key) => after_key,
+                None => break,
+            };
+
+            page = match get_export_page(url_service.as_ref(), user_id, Some(after_key)).await {
+                Ok(page) => page,
+                Err(e) => {
+                    // The status is already sent, so end the body incomplete
+                    error!("Failed to export URLs: {:?}", e);
+                    return;
+                }
+            };
+        }
+
+        if let ExportFormat::Json = format {
+            yield String::from("]");
+        }
+    };
+
+    return Ok((content_type, stream));
+}
+
+#[get("/?include_all&<query..>", rank = 1)]
 async fn get_all_for_admin(
     _admin: Admin,
+    _reader: UrlsReader,
     url_service: Box<dyn UrlService>,
+    query: UrlListQuery,
 ) -> Result<Urls, UrlError> {
-    let urls = url_service.get_all().await?;
+    let urls = url_service.get_all(query.into()).await?;
 
-    return Ok(Urls {
-        values: urls.into_iter().map(|url| Url::from(url)).collect(),
-    });
+    return Ok(Urls::from(urls));
 }
 
-#[get("/?<user_id>", rank = 2)]
+#[get("/?<query..>", rank = 2)]
 async fn get_all_by_user_id(
-    user: User,
+    reader: UrlsReader,
     url_service: Box<dyn UrlService>,
-    user_id: Option<i32>,
+    query: UrlListQuery,
 ) -> Result<Urls, UrlError> {
-    let user_id = match user_id {
+    let user = reader.0;
+    let user_id = match query.user_id {
         Some(user_id) if user.is_admin => user_id,
         _ => user.id,
     };
 
-    let urls = url_service.get_all_by_user_id(user_id).await?;
+    let urls = url_service
+        .get_all_by_user_id(user_id, query.into())
+        .await?;
 
-    return Ok(Urls {
-        values: urls.into_iter().map(|url| Url::from(url)).collect(),
-    });
+    return Ok(Urls::from(urls));
 }
 
 #[get("/<key..>", rank = 3)]
+async fn get_stats_by_key(
+    reader: UrlsReader,
+    url_service: Box<dyn UrlService>,
+    click_service: Box<dyn ClickService>,
+    key: UrlStatsKey,
+) -> Result<ClickStats, UrlError> {
+    let user = reader.0;
+    let key = key.0;
+
+    // Only the owner of the URL, its collaborators, or an admin may view its stats
+    let key = if user.is_admin {
+        match url_service.get_by_key(key.clone()).await {
+            Ok(url) => url.key,
+            // Links outside of their active window still have stats
+            Err(UrlError::NotYetActive | UrlError::Expired) => key,
+            Err(e) => return Err(e),
+        }
+    } else {
+        url_service.get_by_key_for_user(user, key).await?.key
+    };
+
+    let stats = click_service.get_stats_by_key(key).await?;
+
+    return Ok(ClickStats::from(stats));
+}
+
+#[get("/<key..>?<query..>", rank = 4)]
+async fn get_qr_by_key(
+    reader: UrlsReader,
+    url_service: Box<dyn UrlService>,
+    app_config: &State<AppConfig>,
+    key: UrlQrKey,
+    query: QrQuery,
+) -> Result<QrCode, UrlError> {
+    let user = reader.0;
+    let key = key.0;
+
+    // Only the owner of the URL, its collaborators, or an admin may get its QR code
+    let key = if user.is_admin {
+        match url_service.get_by_key(key.clone()).await {
+            Ok(url) => url.key,
+            // Codes may be printed before a link activates
+            Err(UrlError::NotYetActive | UrlError::Expired) => key.to_ascii_lowercase(),
+            Err(e) => return Err(e),
+        }
+    } else {
+        url_service.get_by_key_for_user(user, key).await?.key
+    };
+
+    let short_url = qr::short_url(&app_config.public_base_url, &key);
+    let image = qr::render(&short_url, &query.into())?;
+
+    return Ok(QrCode::from(image));
+}
+
+#[get("/<key..>", rank = 5)]
+async fn get_collaborators(
+    reader: UrlsReader,
+    url_service: Box<dyn UrlService>,
+    key: UrlCollaboratorsKey,
+) -> Result<UrlCollaborators, UrlError> {
+    let collaborators = url_service.get_collaborators(reader.0, key.0).await?;
+
+    return Ok(UrlCollaborators::from(collaborators));
+}
+
+#[get("/<key..>", rank = 6)]
 async fn get_by_key(
-    user: User,
+    reader: UrlsReader,
     url_service: Box<dyn UrlService>,
     key: PathBuf,
 ) -> Result<Url, UrlError> {
+    let user = reader.0;
     let key = key.display().to_string();
 
     let url = url_service.get_by_key_for_user(user, key).await?;
@@ -81,13 +286,46 @@ async fn get_by_key(
     return Ok(Url::from(url));
 }
 
+#[post("/<key..>", data = "<collaborator>", rank = 1)]
+async fn add_collaborator(
+    writer: UrlsWriter,
+    url_service: Box<dyn UrlService>,
+    key: UrlCollaboratorsKey,
+    collaborator: Json<AddUrlCollaborator>,
+) -> Result<UrlCollaborators, UrlError> {
+    let collaborator: AddUrlCollaborator = collaborator.0;
+
+    let collaborators = url_service
+        .add_collaborator(writer.0, key.0, collaborator.user_id)
+        .await?;
+
+    return Ok(UrlCollaborators::from(collaborators));
+}
+
+#[post("/<key..>", data = "<transfer>", rank = 2)]
+async fn transfer_by_key(
+    admin: Admin,
+    url_service: Box<dyn UrlService>,
+    key: UrlTransferKey,
+    transfer: Json<UrlTransfer>,
+) -> Result<Url, UrlError> {
+    let transfer: UrlTransfer = transfer.0;
+
+    let url = url_service
+        .transfer_by_key(admin.0, key.0, transfer.user_id)
+        .await?;
+
+    return Ok(Url::from(url));
+}
+
 #[put("/<key..>", data = "<url>")]
 async fn update_by_key(
-    user: User,
+    writer: UrlsWriter,
     url_service: Box<dyn UrlService>,
     key: PathBuf,
     url: Json<UpdateUrl>,
 ) -> Result<Url, UrlError> {
+    let user = writer.0;
     let key = key.display().to_string();
     let url: UpdateUrl = url.0;
 
@@ -98,12 +336,26 @@ async fn update_by_key(
     return Ok(Url::from(url));
 }
 
-#[delete("/<key..>")]
+#[delete("/<key..>", rank = 1)]
+async fn remove_collaborator(
+    writer: UrlsWriter,
+    url_service: Box<dyn UrlService>,
+    key: UrlCollaboratorPath,
+) -> Result<(), UrlError> {
+    url_service
+        .remove_collaborator(writer.0, key.key, key.user_id)
+        .await?;
+
+    return Ok(());
+}
+
+#[delete("/<key..>", rank = 2)]
 async fn delete_by_key(
-    user: User,
+    writer: UrlsWriter,
     url_service: Box<dyn UrlService>,
     key: PathBuf,
 ) -> Result<(), UrlError> {
+    let user = writer.0;
     let key = key.display().to_string();
 
     url_service.delete_by_key_for_user(user, key).await?;
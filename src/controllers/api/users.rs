@@ -1,14 +1,24 @@
 use rocket::{routes, serde::json::Json, Build, Rocket};
 
-use crate::errors::user::UserError;
+use crate::errors::{token::TokenError, url::UrlError, user::UserError};
 use crate::services::{
-    types::{admin::Admin, user::User as ApiUser},
+    token::TokenService,
+    types::{admin::Admin, auth::ClientUser, user::User as ApiUser},
+    url::UrlService,
     user::UserService,
 };
 
 use super::super::types::{
-    request::user::{CreateUser, UpdateUser, UpdateUserClientSecret},
-    response::user::{User, Users},
+    request::{
+        token::CreateToken,
+        url::UrlTransfer,
+        user::{CreateUser, UpdateUser, UpdateUserClientSecret, UserListQuery},
+    },
+    response::{
+        token::{CreatedToken, Tokens},
+        url::TransferredUrls,
+        user::{User, UserLockout, Users},
+    },
 };
 
 pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
@@ -17,10 +27,16 @@ pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
         routes![
             get_self,
             update_self,
+            create_self_token,
+            get_self_tokens,
+            delete_self_token,
             create,
             get_all,
             get_by_id,
             update_by_id,
+            transfer_urls,
+            get_lockout,
+            clear_lockout,
             delete_by_id
         ],
     );
@@ -35,10 +51,11 @@ async fn get_self(user: ApiUser, user_service: Box<dyn UserService>) -> Result<U
 
 #[put("/self", data = "<user_client_secret>")]
 async fn update_self(
-    user: ApiUser,
+    user: ClientUser,
     user_service: Box<dyn UserService>,
     user_client_secret: Json<UpdateUserClientSecret>,
 ) -> Result<User, UserError> {
+    let user = user.0;
     let body: UpdateUserClientSecret = user_client_secret.0;
 
     let user = user_service
@@ -48,30 +65,62 @@ async fn update_self(
     return Ok(User::from(user));
 }
 
+#[post("/self/tokens", data = "<token>")]
+async fn create_self_token(
+    user: ClientUser,
+    token_service: Box<dyn TokenService>,
+    token: Json<CreateToken>,
+) -> Result<CreatedToken, TokenError> {
+    let token: CreateToken = token.0;
+
+    let token = token_service.create(user.0, token.into()).await?;
+
+    return Ok(CreatedToken::from(token));
+}
+
+#[get("/self/tokens")]
+async fn get_self_tokens(
+    user: ClientUser,
+    token_service: Box<dyn TokenService>,
+) -> Result<Tokens, TokenError> {
+    let tokens = token_service.get_all_for_user(user.0).await?;
+
+    return Ok(Tokens::from(tokens));
+}
+
+#[delete("/self/tokens/<id>")]
+async fn delete_self_token(
+    user: ClientUser,
+    token_service: Box<dyn TokenService>,
+    id: i32,
+) -> Result<(), TokenError> {
+    token_service.delete_by_id_for_user(user.0, id).await?;
+
+    return Ok(());
+}
+
 #[post("/", data = "<user>")]
 async fn create(
-    _admin: Admin,
+    admin: Admin,
     user_service: Box<dyn UserService>,
     user: Json<CreateUser>,
 ) -> Result<User, UserError> {
     let user: CreateUser = user.0;
 
-    let user = user_service.create(user.into()).await?;
+    let user = user_service.create(admin.0, user.into()).await?;
 
     return Ok(User::from(user));
 }
 
-#[get("/?<client_id>")]
+#[get("/?<query..>")]
 async fn get_all(
     _admin: Admin,
     user_service: Box<dyn UserService>,
-    client_id: Option<String>,
+    query: UserListQuery,
 ) -> Result<Users, UserError> {
-    let users = user_service.get_all(client_id).await?;
+    let users = user_service.get_all(query.into()).await?;
 
-    return Ok(Users {
-        values: users.into_iter().map(|user| User::from(user)).collect(),
-    });
+    return Ok(Users::from(users));
 }
 
 #[get("/<id>")]
@@ -87,25 +136,65 @@ async fn get_by_id(
 
 #[put("/<id>", data = "<user>")]
 async fn update_by_id(
-    _admin: Admin,
+    admin: Admin,
     user_service: Box<dyn UserService>,
     id: i32,
     user: Json<UpdateUser>,
 ) -> Result<User, UserError> {
     let user: UpdateUser = user.0;
 
-    let user = user_service.update_by_id(id, user.into()).await?;
+    let user = user_service.update_by_id(admin.0, id, user.into()).await?;
 
     return Ok(User::from(user));
 }
 
+/// Moves every URL of the user to another user, for example before the user
+/// is deleted.
+#[post("/<id>/transfer", data = "<transfer>")]
+async fn transfer_urls(
+    admin: Admin,
+    url_service: Box<dyn UrlService>,
+    id: i32,
+    transfer: Json<UrlTransfer>,
+) -> Result<TransferredUrls, UrlError> {
+    let transfer: UrlTransfer = transfer.0;
+
+    let transferred = url_service
+        .transfer_by_user_id(admin.0, id, transfer.user_id)
+        .await?;
+
+    return Ok(TransferredUrls { transferred });
+}
+
+#[get("/<id>/lockout")]
+async fn get_lockout(
+    _admin: Admin,
+    user_service: Box<dyn UserService>,
+    id: i32,
+) -> Result<UserLockout, UserError> {
+    let lockout = user_service.get_lockout(id).await?;
+
+    return Ok(UserLockout::from(lockout));
+}
+
+#[delete("/<id>/lockout")]
+async fn clear_lockout(
+    admin: Admin,
+    user_service: Box<dyn UserService>,
+    id: i32,
+) -> Result<(), UserError> {
+    user_service.clear_lockout(admin.0, id).await?;
+
+    return Ok(());
+}
+
 #[delete("/<id>")]
 async fn delete_by_id(
-    _admin: Admin,
+    admin: Admin,
     user_service: Box<dyn UserService>,
     id: i32,
 ) -> Result<(), UserError> {
-    user_service.delete_by_id(id).await?;
+    user_service.delete_by_id(admin.0, id).await?;
 
     return Ok(());
 }
@@ -1,13 +1,26 @@
 use rocket::{
     fairing::{Fairing, Info, Kind},
-    http::Header,
-    Build, Request, Response, Rocket,
+    http::{Header, Method, Status},
+    routes, Build, Request, Response, Rocket,
 };
 
-pub fn attach(rocket: Rocket<Build>) -> Rocket<Build> {
-    return rocket.attach(CORS);
+use crate::config::cors::CorsPolicy;
+
+pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
+    return rocket.attach(CORS).mount("/", routes![preflight]);
+}
+
+/// Answers CORS preflight requests for every path. The fairing adds the
+/// headers that allow the actual request, if the origin is allowed.
+#[options("/<_..>")]
+async fn preflight() -> Status {
+    return Status::NoContent;
 }
 
+/// Headers that scripts of allowed origins can read from responses
+const EXPOSED_HEADERS: &str =
+    "Location, Retry-After, X-RateLimit-Limit, X-RateLimit-Remaining, X-RateLimit-Reset";
+
 pub struct CORS;
 
 #[rocket::async_trait]
@@ -19,13 +32,48 @@ impl Fairing for CORS {
         }
     }
 
-    async fn on_response<'r>(&self, _request: &'r Request<'_>, response: &mut Response<'r>) {
-        response.set_header(Header::new("Access-Control-Allow-Origin", "*"));
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let origin = match request.headers().get_one("Origin") {
+            Some(origin) => origin,
+            None => return,
+        };
+
+        // The response depends on the origin, whether or not it is allowed
+        response.adjoin_header(Header::new("Vary", "Origin"));
+
+        let policy = match request.rocket().state::<CorsPolicy>() {
+            Some(policy) if policy.allows(origin) => policy,
+            _ => return,
+        };
+
+        response.set_header(Header::new(
+            "Access-Control-Allow-Origin",
+            String::from(origin),
+        ));
+        response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
+
+        let is_preflight = request.method() == Method::Options
+            && request.headers().contains("Access-Control-Request-Method");
+
+        if !is_preflight {
+            response.set_header(Header::new(
+                "Access-Control-Expose-Headers",
+                EXPOSED_HEADERS,
+            ));
+            return;
+        }
+
         response.set_header(Header::new(
             "Access-Control-Allow-Methods",
             "POST, GET, PUT, DELETE, OPTIONS",
         ));
-        response.set_header(Header::new("Access-Control-Allow-Headers", "*"));
-        response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
+        response.set_header(Header::new(
+            "Access-Control-Allow-Headers",
+            policy.allowed_headers.join(", "),
+        ));
+        response.set_header(Header::new(
+            "Access-Control-Max-Age",
+            policy.max_age_seconds.to_string(),
+        ));
     }
 }
@@ -0,0 +1,68 @@
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+};
+
+use crate::services::types::{
+    auth::{Authentication, ClientUser, UrlsReader, UrlsWriter},
+    token::Scope,
+    user::User,
+};
+
+use super::user::{authenticate, UserCredentialsError};
+
+async fn authenticate_with_scope(
+    req: &Request<'_>,
+    scope: Scope,
+) -> Outcome<User, UserCredentialsError> {
+    return match authenticate(req).await {
+        Outcome::Success((user, authentication)) if authentication.allows(scope) => {
+            Outcome::Success(user)
+        }
+        Outcome::Success(_) => {
+            Outcome::Failure((Status::Forbidden, UserCredentialsError::ScopeMissing))
+        }
+        Outcome::Failure(e) => Outcome::Failure(e),
+        Outcome::Forward(e) => Outcome::Forward(e),
+    };
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for UrlsReader {
+    type Error = UserCredentialsError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        return authenticate_with_scope(req, Scope::UrlsRead)
+            .await
+            .map(UrlsReader);
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for UrlsWriter {
+    type Error = UserCredentialsError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        return authenticate_with_scope(req, Scope::UrlsWrite)
+            .await
+            .map(UrlsWriter);
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientUser {
+    type Error = UserCredentialsError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        return match authenticate(req).await {
+            Outcome::Success((user, Authentication::ClientCredentials)) => {
+                Outcome::Success(ClientUser(user))
+            }
+            Outcome::Success(_) => {
+                Outcome::Failure((Status::Forbidden, UserCredentialsError::ScopeMissing))
+            }
+            Outcome::Failure(e) => Outcome::Failure(e),
+            Outcome::Forward(e) => Outcome::Forward(e),
+        };
+    }
+}
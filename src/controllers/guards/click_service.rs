@@ -0,0 +1,25 @@
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+    Orbit, Rocket,
+};
+
+use crate::config::database::DbConnection;
+use crate::services::click::{ClickService, DbClickService};
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Box<dyn ClickService> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        return build_click_service(req.rocket()).await;
+    }
+}
+
+/// Builds the click service, checking a connection out of the pool.
+pub async fn build_click_service(rocket: &Rocket<Orbit>) -> Outcome<Box<dyn ClickService>, ()> {
+    return match DbConnection::get_one(rocket).await {
+        Some(db) => Outcome::Success(Box::new(DbClickService::new(db))),
+        None => Outcome::Failure((Status::ServiceUnavailable, ())),
+    };
+}
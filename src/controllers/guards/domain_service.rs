@@ -0,0 +1,17 @@
+use rocket::request::{FromRequest, Outcome, Request};
+
+use crate::config::database::DbConnection;
+use crate::services::domain::{DbDomainRuleService, DomainRuleService};
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Box<dyn DomainRuleService> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        return match req.guard::<DbConnection>().await {
+            Outcome::Success(db) => Outcome::Success(Box::new(DbDomainRuleService::new(db))),
+            Outcome::Failure(e) => Outcome::Failure(e),
+            Outcome::Forward(e) => Outcome::Forward(e),
+        };
+    }
+}
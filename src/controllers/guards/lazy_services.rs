@@ -0,0 +1,47 @@
+use std::convert::Infallible;
+
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+};
+
+use crate::errors::{database::DatabaseError, url::UrlError};
+use crate::services::{click::ClickService, url::UrlService, user::UserService};
+
+use super::super::query::LazyServices;
+use super::click_service::build_click_service;
+use super::url_service::build_url_service;
+use super::user_service::build_user_service;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LazyServices<'r> {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        return Outcome::Success(LazyServices(req.rocket()));
+    }
+}
+
+impl LazyServices<'_> {
+    pub async fn url_service(&self) -> Result<Box<dyn UrlService>, UrlError> {
+        return into_result(build_url_service(self.0).await);
+    }
+
+    pub async fn click_service(&self) -> Result<Box<dyn ClickService>, UrlError> {
+        return into_result(build_click_service(self.0).await);
+    }
+
+    pub async fn user_service(&self) -> Result<Box<dyn UserService>, UrlError> {
+        return into_result(build_user_service(self.0).await);
+    }
+}
+
+fn into_result<S>(outcome: Outcome<S, ()>) -> Result<S, UrlError> {
+    return match outcome {
+        Outcome::Success(service) => Ok(service),
+        Outcome::Failure((status, _)) if status == Status::ServiceUnavailable => {
+            Err(UrlError::Database(DatabaseError::unavailable()))
+        }
+        _ => Err(UrlError::Unknown),
+    };
+}
@@ -1,4 +1,13 @@
 mod admin;
+mod audit_service;
+mod auth;
+mod click_service;
+mod domain_service;
+mod lazy_services;
+mod rate_limit;
+mod redirector;
+mod token_service;
 mod url_service;
 mod user;
 mod user_service;
+mod visitor;
@@ -0,0 +1,85 @@
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+};
+
+use crate::config::rate_limit::RateLimits;
+use crate::services::{
+    rate_limit::RateLimiterRef,
+    types::rate_limit::{RateLimitState, VisitorRateLimit},
+};
+
+use super::super::rate_limit::record;
+
+fn get_limiter(
+    req: &Request<'_>,
+    group: fn(&RateLimits) -> &Option<RateLimiterRef>,
+) -> Option<RateLimiterRef> {
+    return req
+        .rocket()
+        .state::<RateLimits>()
+        .and_then(|limits| group(limits).clone());
+}
+
+fn get_ip(req: &Request<'_>) -> String {
+    return match req.client_ip() {
+        Some(ip) => ip.to_string(),
+        None => String::from("unknown"),
+    };
+}
+
+fn check(req: &Request<'_>, state: RateLimitState) -> Result<(), Status> {
+    record(req, state);
+
+    return match state.retry_after {
+        Some(_) => Err(Status::TooManyRequests),
+        None => Ok(()),
+    };
+}
+
+/// Takes one API request from a verified client's bucket.
+pub fn take_api(req: &Request<'_>, client_id: &str) -> Result<(), Status> {
+    return match get_limiter(req, |limits| &limits.api) {
+        Some(limiter) => check(req, limiter.take(client_id)),
+        None => Ok(()),
+    };
+}
+
+/// Rejects requests from an IP that failed authentication too often.
+pub fn check_auth_failures(req: &Request<'_>) -> Result<(), Status> {
+    let limiter = match get_limiter(req, |limits| &limits.auth_failures) {
+        Some(limiter) => limiter,
+        None => return Ok(()),
+    };
+
+    let state = limiter.peek(&get_ip(req));
+
+    return match state.retry_after {
+        Some(_) => check(req, state),
+        None => Ok(()),
+    };
+}
+
+/// Counts a failed authentication against the request's IP.
+pub fn record_auth_failure(req: &Request<'_>) {
+    if let Some(limiter) = get_limiter(req, |limits| &limits.auth_failures) {
+        limiter.take(&get_ip(req));
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for VisitorRateLimit {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let result = match get_limiter(req, |limits| &limits.visitor) {
+            Some(limiter) => check(req, limiter.take(&get_ip(req))),
+            None => Ok(()),
+        };
+
+        return match result {
+            Ok(()) => Outcome::Success(VisitorRateLimit),
+            Err(status) => Outcome::Failure((status, ())),
+        };
+    }
+}
@@ -0,0 +1,40 @@
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+};
+
+use crate::clicks::ClickQueue;
+use crate::config::redirect::DefaultRedirectType;
+use crate::services::types::click::Visitor;
+
+use super::super::query::Redirector;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Redirector<'r> {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let default_redirect_type = match req.rocket().state::<DefaultRedirectType>() {
+            Some(default_redirect_type) => default_redirect_type,
+            None => return Outcome::Failure((Status::InternalServerError, ())),
+        };
+
+        let click_queue = match req.rocket().state::<ClickQueue>() {
+            Some(click_queue) => click_queue,
+            None => return Outcome::Failure((Status::InternalServerError, ())),
+        };
+
+        let visitor = match req.guard::<Visitor>().await {
+            Outcome::Success(visitor) => visitor,
+            Outcome::Failure((_, e)) => match e {},
+            Outcome::Forward(e) => return Outcome::Forward(e),
+        };
+
+        return Outcome::Success(Redirector {
+            click_queue,
+            default_redirect_type,
+            visitor,
+            origin: req.uri(),
+        });
+    }
+}
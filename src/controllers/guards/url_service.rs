@@ -1,17 +1,64 @@
-use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+    Orbit, Rocket,
+};
 
-use crate::config::database::DbConnection;
-use crate::services::url::{DbUrlService, UrlService};
+use crate::config::{app::AppConfig, database::DbConnection, deletion::DeletionRetention};
+use crate::services::{
+    cache::RedirectCacheRef,
+    key::KeyGeneratorRef,
+    password::{Argon2ConfigRef, Argon2PasswordService},
+    url::{DbUrlService, UrlService, UrlSettings},
+};
 
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for Box<dyn UrlService> {
     type Error = ();
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        return match req.guard::<DbConnection>().await {
-            Outcome::Success(db) => Outcome::Success(DbUrlService::new(db)),
-            Outcome::Failure(e) => Outcome::Failure(e),
-            Outcome::Forward(e) => Outcome::Forward(e),
-        };
+        return build_url_service(req.rocket()).await;
     }
 }
+
+/// Builds the URL service from managed state, checking a connection out of
+/// the pool.
+pub async fn build_url_service(rocket: &Rocket<Orbit>) -> Outcome<Box<dyn UrlService>, ()> {
+    let (app_config, argon2_config, key_generator, retention) = match (
+        rocket.state::<AppConfig>(),
+        rocket.state::<Argon2ConfigRef>(),
+        rocket.state::<KeyGeneratorRef>(),
+        rocket.state::<DeletionRetention>(),
+    ) {
+        (Some(app_config), Some(argon2_config), Some(key_generator), Some(retention)) => {
+            (app_config, argon2_config, key_generator, retention.0)
+        }
+        _ => return Outcome::Failure((Status::InternalServerError, ())),
+    };
+
+    return match DbConnection::get_one(rocket).await {
+        Some(db) => {
+            let key_generator = KeyGeneratorRef::clone(key_generator);
+            let redirect_cache = rocket
+                .state::<Option<RedirectCacheRef>>()
+                .and_then(|redirect_cache| redirect_cache.clone());
+
+            let argon2_config = Argon2ConfigRef::clone(argon2_config);
+            let public_base_url = url::Url::parse(&app_config.public_base_url).ok();
+
+            Outcome::Success(DbUrlService::new(
+                db,
+                key_generator,
+                redirect_cache,
+                Argon2PasswordService::new(argon2_config),
+                UrlSettings {
+                    public_base_url,
+                    retention,
+                    key_limits: app_config.keys.clone(),
+                    passphrase_limits: app_config.passphrases,
+                },
+            ))
+        }
+        None => Outcome::Failure((Status::ServiceUnavailable, ())),
+    };
+}
@@ -1,75 +1,165 @@
+use std::time::Duration;
+
 use rocket::{
     http::Status,
     request::{FromRequest, Outcome, Request},
 };
 
-use crate::services::{types::user::User, user::UserService};
-use crate::utils;
+use crate::config::app::AppConfig;
+use crate::errors::user::UserError;
+use crate::services::{
+    token::TokenService,
+    types::{auth::Authentication, token::Scope, user::User},
+    user::UserService,
+};
 
-lazy_static! {
-    static ref HEADER_CLIENT_ID: String = utils::required_env_var("HEADER_CLIENT_ID");
-    static ref HEADER_CLIENT_SECRET: String = utils::required_env_var("HEADER_CLIENT_SECRET");
-}
+use super::super::rate_limit::record_lockout;
+use super::rate_limit;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum UserCredentialsError {
     Missing,
     Invalid,
+    ScopeMissing,
     NoUserService,
+    NoTokenService,
+    NoConfig,
+    RateLimited,
+    LockedOut,
     Unknown,
 }
 
-#[rocket::async_trait]
-impl<'r> FromRequest<'r> for User {
-    type Error = UserCredentialsError;
+type AuthenticationResult = Result<(User, Authentication), (Status, UserCredentialsError)>;
 
-    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        let headers = req.headers();
-
-        // Get client credentials from request headers
-        let (client_id, client_secret) = match (
-            headers.get_one(HEADER_CLIENT_ID.as_str()),
-            headers.get_one(HEADER_CLIENT_SECRET.as_str()),
-        ) {
-            (Some(client_id), Some(client_secret)) => {
-                (String::from(client_id), String::from(client_secret))
-            }
-            _ => {
-                return Outcome::Failure((Status::Unauthorized, UserCredentialsError::Missing));
+/// Authenticates the request once, with either a bearer token or client
+/// credentials. Every user guard on the same request shares the result.
+pub async fn authenticate(
+    req: &Request<'_>,
+) -> Outcome<(User, Authentication), UserCredentialsError> {
+    let result: &AuthenticationResult = req
+        .local_cache_async(async {
+            if let Err(status) = rate_limit::check_auth_failures(req) {
+                return Err((status, UserCredentialsError::RateLimited));
             }
-        };
-
-        // Get UserService from request guards
-        let user_service = match get_user_service(req).await {
-            Outcome::Success(user_service) => user_service,
-            Outcome::Failure(e) => return Outcome::Failure(e),
-            _ => {
-                return Outcome::Failure((
-                    Status::InternalServerError,
-                    UserCredentialsError::Unknown,
-                ))
+
+            let (user, authentication) = match get_bearer_token(req) {
+                Some(token) => authenticate_token(req, token).await,
+                None => authenticate_client_credentials(req).await,
+            }?;
+
+            // Only verified clients are charged, or anyone could spend another
+            // client's quota by sending its ID with a wrong secret. Failures
+            // count against the IP instead.
+            if let Err(status) = rate_limit::take_api(req, &user.client_id) {
+                return Err((status, UserCredentialsError::RateLimited));
             }
-        };
 
-        // Get verified user
-        return match user_service.verify_and_get(client_id, client_secret).await {
-            Ok(user) => Outcome::Success(user),
-            _ => Outcome::Failure((Status::Unauthorized, UserCredentialsError::Invalid)),
-        };
+            return Ok((user, authentication));
+        })
+        .await;
+
+    return match result {
+        Ok(authenticated) => Outcome::Success(authenticated.clone()),
+        Err(e) => Outcome::Failure(e.clone()),
+    };
+}
+
+fn get_bearer_token(req: &Request<'_>) -> Option<String> {
+    let authorization = req.headers().get_one("Authorization")?;
+
+    let (scheme, token) = authorization.split_once(' ')?;
+
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
     }
+
+    return Some(String::from(token.trim()));
 }
 
-async fn get_user_service(
-    req: &Request<'_>,
-) -> Outcome<Box<dyn UserService>, UserCredentialsError> {
-    let user_service_outcome = req.guard::<Box<dyn UserService>>().await;
-
-    if let Outcome::Success(user_service) = user_service_outcome {
-        return Outcome::Success(user_service);
-    } else {
-        return Outcome::Failure((
-            Status::InternalServerError,
-            UserCredentialsError::NoUserService,
-        ));
+async fn authenticate_token(req: &Request<'_>, token: String) -> AuthenticationResult {
+    let token_service = match req.guard::<Box<dyn TokenService>>().await {
+        Outcome::Success(token_service) => token_service,
+        _ => {
+            return Err((
+                Status::InternalServerError,
+                UserCredentialsError::NoTokenService,
+            ))
+        }
+    };
+
+    let (mut user, token) = match token_service.verify_and_get(token).await {
+        Ok(verified) => verified,
+        _ => {
+            rate_limit::record_auth_failure(req);
+            return Err((Status::Unauthorized, UserCredentialsError::Invalid));
+        }
+    };
+
+    // Admin rights are only delegated to tokens that were granted them
+    user.is_admin = user.is_admin && token.scopes.contains(&Scope::UsersAdmin);
+
+    return Ok((
+        user,
+        Authentication::Token {
+            scopes: token.scopes,
+        },
+    ));
+}
+
+async fn authenticate_client_credentials(req: &Request<'_>) -> AuthenticationResult {
+    let headers = req.headers();
+
+    let header_names = match req.rocket().state::<AppConfig>() {
+        Some(app_config) => &app_config.headers,
+        None => return Err((Status::InternalServerError, UserCredentialsError::NoConfig)),
+    };
+
+    // Get client credentials from request headers
+    let (client_id, client_secret) = match (
+        headers.get_one(&header_names.client_id),
+        headers.get_one(&header_names.client_secret),
+    ) {
+        (Some(client_id), Some(client_secret)) => {
+            (String::from(client_id), String::from(client_secret))
+        }
+        _ => {
+            return Err((Status::Unauthorized, UserCredentialsError::Missing));
+        }
+    };
+
+    // Get UserService from request guards
+    let user_service = match req.guard::<Box<dyn UserService>>().await {
+        Outcome::Success(user_service) => user_service,
+        _ => {
+            return Err((
+                Status::InternalServerError,
+                UserCredentialsError::NoUserService,
+            ))
+        }
+    };
+
+    // Get verified user
+    return match user_service
+        .verify_and_get(client_id, client_secret, req.client_ip())
+        .await
+    {
+        Ok(user) => Ok((user, Authentication::ClientCredentials)),
+        Err(UserError::LockedOut { retry_after }) => {
+            record_lockout(req, Duration::from_secs(retry_after));
+            Err((Status::TooManyRequests, UserCredentialsError::LockedOut))
+        }
+        _ => {
+            rate_limit::record_auth_failure(req);
+            Err((Status::Unauthorized, UserCredentialsError::Invalid))
+        }
+    };
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for User {
+    type Error = UserCredentialsError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        return authenticate(req).await.map(|(user, _)| user);
     }
 }
@@ -1,46 +1,57 @@
-use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+    Orbit, Rocket,
+};
 
-use crate::config::database::DbConnection;
+use crate::config::{app::AppConfig, database::DbConnection, lockout::CredentialLockouts};
 use crate::services::{
-    password::{Argon2Config, Argon2ConfigRef, Argon2PasswordService},
-    url::UrlService,
+    cache::RedirectCacheRef,
+    password::{Argon2ConfigRef, Argon2PasswordService},
     user::{DbUserService, UserService},
 };
-use crate::utils;
-
-lazy_static! {
-    static ref HASHER_SECRET: String = utils::required_env_var("HASHER_SECRET");
-    static ref ARGON2_CONFIG: Argon2ConfigRef = build_argon2_config_ref();
-}
 
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for Box<dyn UserService> {
     type Error = ();
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        return match (
-            req.guard::<DbConnection>().await,
-            req.guard::<Box<dyn UrlService>>().await,
-        ) {
-            (Outcome::Success(db), Outcome::Success(url_service)) => {
-                let argon2_config = Argon2ConfigRef::clone(&ARGON2_CONFIG);
-
-                Outcome::Success(DbUserService::new(
-                    db,
-                    url_service,
-                    Argon2PasswordService::new(argon2_config),
-                ))
-            }
-            (Outcome::Failure(e), _) | (_, Outcome::Failure(e)) => Outcome::Failure(e),
-            (Outcome::Forward(e), _) | (_, Outcome::Forward(e)) => Outcome::Forward(e),
-        };
+        return build_user_service(req.rocket()).await;
     }
 }
 
-fn build_argon2_config_ref() -> Argon2ConfigRef {
-    return Argon2ConfigRef::new(Argon2Config {
-        secret: HASHER_SECRET.as_bytes(),
-        variant: argon2::Variant::Argon2id,
-        ..Argon2Config::default()
-    });
+/// Builds the user service from managed state, checking a connection out of
+/// the pool.
+pub async fn build_user_service(rocket: &Rocket<Orbit>) -> Outcome<Box<dyn UserService>, ()> {
+    let (app_config, argon2_config, lockouts) = match (
+        rocket.state::<AppConfig>(),
+        rocket.state::<Argon2ConfigRef>(),
+        rocket.state::<CredentialLockouts>(),
+    ) {
+        (Some(app_config), Some(argon2_config), Some(lockouts)) => {
+            (app_config, argon2_config, lockouts)
+        }
+        _ => return Outcome::Failure((Status::InternalServerError, ())),
+    };
+
+    return match DbConnection::get_one(rocket).await {
+        Some(db) => {
+            let argon2_config = Argon2ConfigRef::clone(argon2_config);
+            let redirect_cache = rocket
+                .state::<Option<RedirectCacheRef>>()
+                .and_then(|redirect_cache| redirect_cache.clone());
+            let public_base_url = url::Url::parse(&app_config.public_base_url).ok();
+
+            Outcome::Success(DbUserService::new(
+                db,
+                Argon2PasswordService::new(argon2_config),
+                redirect_cache,
+                lockouts.clone(),
+                public_base_url,
+                app_config.client_ids,
+                app_config.client_secrets,
+            ))
+        }
+        None => Outcome::Failure((Status::ServiceUnavailable, ())),
+    };
 }
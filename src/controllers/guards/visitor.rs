@@ -0,0 +1,20 @@
+use std::convert::Infallible;
+
+use rocket::request::{FromRequest, Outcome, Request};
+
+use crate::services::types::click::Visitor;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Visitor {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let headers = req.headers();
+
+        return Outcome::Success(Visitor {
+            referrer: headers.get_one("Referer").map(String::from),
+            user_agent: headers.get_one("User-Agent").map(String::from),
+            ip: req.client_ip(),
+        });
+    }
+}
@@ -7,11 +7,13 @@ mod api;
 mod cors;
 mod guards;
 mod query;
+mod rate_limit;
 mod responders;
 mod types;
 
 pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
-    let rocket = cors::attach(rocket);
+    let rocket = cors::mount(rocket);
+    let rocket = rate_limit::attach(rocket);
 
     let rocket = rocket.mount(
         "/client",
@@ -1,13 +1,35 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
-use rocket::{http::uri::Reference, response::Redirect, routes, Build, Rocket};
+use rocket::{
+    form::Form,
+    http::{
+        uri::{Origin, Reference},
+        Status,
+    },
+    response::Redirect,
+    routes, Build, Orbit, Rocket, State,
+};
 
+use crate::clicks::ClickQueue;
+use crate::config::{redirect::DefaultRedirectType, unlock::UnlockLimiter};
 use crate::errors::url::UrlError;
-use crate::services::types::url::Url;
-use crate::services::url::UrlService;
+use crate::services::cache::RedirectCacheRef;
+use crate::services::types::{
+    click::{CreateClickRequest, Visitor},
+    rate_limit::VisitorRateLimit,
+    url::{RedirectType, ResolvedUrl, Url},
+};
+use crate::services::url::get_cached_by_path;
+
+use super::api::preview::build_preview;
+use super::types::{
+    request::url::UnlockUrl,
+    response::{preview::UrlPreviewPage, unlock::UnlockPage},
+};
 
 pub fn mount(rocket: Rocket<Build>) -> Rocket<Build> {
-    return rocket.mount("/", routes![index, query]);
+    return rocket.mount("/", routes![index, query, unlock]);
 }
 
 #[get("/")]
@@ -15,13 +37,162 @@ async fn index() -> Redirect {
     return Redirect::to(uri!("/client"));
 }
 
+#[derive(Responder)]
+enum QueryResponse {
+    Redirect(Redirect),
+    Unlock(UnlockPage),
+    Preview(UrlPreviewPage),
+}
+
+/// Sends a visitor on to the destination of a resolved URL, recording the click.
+pub struct Redirector<'r> {
+    pub click_queue: &'r ClickQueue,
+    pub default_redirect_type: &'r DefaultRedirectType,
+    pub visitor: Visitor,
+    pub origin: &'r Origin<'r>,
+}
+
+/// Builds services on demand, so that redirects served from the redirect
+/// cache never take a database connection.
+pub struct LazyServices<'r>(pub &'r Rocket<Orbit>);
+
+/// Resolves a path from the redirect cache, taking a connection only on a miss.
+async fn resolve(services: &LazyServices<'_>, path: String) -> Result<ResolvedUrl, UrlError> {
+    if let Some(Some(redirect_cache)) = services.0.state::<Option<RedirectCacheRef>>() {
+        if let Some(resolved) = get_cached_by_path(redirect_cache, &path) {
+            return resolved;
+        }
+    }
+
+    return services.url_service().await?.get_by_path(path).await;
+}
+
 #[get("/<key..>", rank = 11)]
-async fn query(url_service: Box<dyn UrlService>, key: PathBuf) -> Result<Redirect, UrlError> {
-    let key = key.display().to_string();
+async fn query(
+    _rate_limit: VisitorRateLimit,
+    services: LazyServices<'_>,
+    redirector: Redirector<'_>,
+    key: PathBuf,
+) -> Result<QueryResponse, UrlError> {
+    let path = key.display().to_string();
+
+    let resolved = match resolve(&services, path.clone()).await {
+        Ok(resolved) => resolved,
+        // Appending `+` to a key previews it, unless a key really ends in `+`
+        Err(UrlError::NotFound) if path.len() > 1 && path.ends_with('+') => {
+            let key = String::from(&path[..path.len() - 1]);
+            let preview = build_preview(
+                services.url_service().await?,
+                services.user_service().await?,
+                services.click_service().await?,
+                key,
+            )
+            .await?;
+
+            return Ok(QueryResponse::Preview(UrlPreviewPage(preview)));
+        }
+        Err(e) => return Err(e),
+    };
+
+    if resolved.url.passphrase_hash.is_some() {
+        return Ok(QueryResponse::Unlock(UnlockPage {
+            status: Status::Ok,
+            action: redirector.origin.to_string(),
+            error: None,
+            retry_after: None,
+        }));
+    }
+
+    let redirect = redirector.redirect(resolved)?;
+
+    return Ok(QueryResponse::Redirect(redirect));
+}
+
+#[post("/<key..>", data = "<unlock>", rank = 11)]
+async fn unlock(
+    _rate_limit: VisitorRateLimit,
+    services: LazyServices<'_>,
+    unlock_limiter: &State<UnlockLimiter>,
+    redirector: Redirector<'_>,
+    key: PathBuf,
+    unlock: Form<UnlockUrl>,
+) -> Result<QueryResponse, UrlError> {
+    let path = key.display().to_string();
+
+    let resolved = resolve(&services, path).await?;
+
+    // Failures are counted per URL and visitor, so one visitor guessing can't
+    // lock everyone else out of the link
+    let attempt_id = match redirector.visitor.ip {
+        Some(ip) => format!("{}|{}", resolved.url.key, ip),
+        None => resolved.url.key.clone(),
+    };
+
+    let locked_page = |retry_after: Duration| {
+        return QueryResponse::Unlock(UnlockPage {
+            status: Status::TooManyRequests,
+            action: redirector.origin.to_string(),
+            error: Some(String::from("Too many failed attempts, try again later.")),
+            retry_after: Some(retry_after.as_secs().max(1)),
+        });
+    };
+
+    if let Some(retry_after) = unlock_limiter.0.retry_after(&attempt_id) {
+        return Ok(locked_page(retry_after));
+    }
+
+    let unlock = unlock.into_inner();
+
+    if !services
+        .url_service()
+        .await?
+        .verify_passphrase(&resolved.url, unlock.passphrase)
+        .await?
+    {
+        if let Some(lockout) = unlock_limiter.0.record_failure(&attempt_id) {
+            warn!("Locked out passphrase attempts for {}", attempt_id);
+            return Ok(locked_page(lockout));
+        }
+
+        return Ok(QueryResponse::Unlock(UnlockPage {
+            status: Status::Unauthorized,
+            action: redirector.origin.to_string(),
+            error: Some(String::from("The passphrase is incorrect.")),
+            retry_after: None,
+        }));
+    }
+
+    unlock_limiter.0.clear(&attempt_id);
+
+    let redirect = redirector.redirect(resolved)?;
+
+    return Ok(QueryResponse::Redirect(redirect));
+}
+
+impl Redirector<'_> {
+    /// Queues the click on a resolved URL and builds the redirect to its
+    /// destination.
+    fn redirect(self, resolved: ResolvedUrl) -> Result<Redirect, UrlError> {
+        let url = resolved.destination(self.origin.query().map(|query| query.as_str()))?;
+        let Url {
+            key, redirect_type, ..
+        } = resolved.url;
 
-    let Url { url, .. } = url_service.get_by_key(key).await?;
+        self.click_queue.record(CreateClickRequest {
+            key,
+            visitor: self.visitor,
+        });
 
-    let reference = Reference::try_from(url).map_err(|_| UrlError::UnexpectedUrlParseError)?;
+        let reference = Reference::try_from(url).map_err(|_| UrlError::UnexpectedUrlParseError)?;
 
-    return Ok(Redirect::to(reference));
+        return Ok(
+            match redirect_type.unwrap_or(self.default_redirect_type.0) {
+                RedirectType::MovedPermanently => Redirect::moved(reference),
+                RedirectType::Found => Redirect::found(reference),
+                RedirectType::SeeOther => Redirect::to(reference),
+                RedirectType::TemporaryRedirect => Redirect::temporary(reference),
+                RedirectType::PermanentRedirect => Redirect::permanent(reference),
+            },
+        );
+    }
 }
@@ -0,0 +1,93 @@
+use std::{sync::Mutex, time::Duration};
+
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    http::Header,
+    Build, Request, Response, Rocket,
+};
+
+use crate::services::types::rate_limit::RateLimitState;
+
+pub fn attach(rocket: Rocket<Build>) -> Rocket<Build> {
+    return rocket.attach(RateLimitHeaders);
+}
+
+/// The rate limit that applied to the request, if any.
+#[derive(Default)]
+struct RecordedRateLimit(Mutex<Option<RateLimitState>>);
+
+/// Remembers the rate limit state to report in the response headers. The
+/// last recorded state wins.
+pub fn record(req: &Request<'_>, state: RateLimitState) {
+    let recorded = req.local_cache(RecordedRateLimit::default);
+
+    *recorded.0.lock().unwrap() = Some(state);
+}
+
+/// How long a locked out client has to wait, if it was locked out.
+#[derive(Default)]
+struct RecordedLockout(Mutex<Option<Duration>>);
+
+/// Remembers a credential lockout, so that the response tells the client
+/// when to retry like a rate limited one.
+pub fn record_lockout(req: &Request<'_>, retry_after: Duration) {
+    let recorded = req.local_cache(RecordedLockout::default);
+
+    *recorded.0.lock().unwrap() = Some(retry_after);
+}
+
+pub struct RateLimitHeaders;
+
+#[rocket::async_trait]
+impl Fairing for RateLimitHeaders {
+    fn info(&self) -> Info {
+        Info {
+            name: "Add rate limit headers to responses",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let lockout = *request
+            .local_cache(RecordedLockout::default)
+            .0
+            .lock()
+            .unwrap();
+
+        if let Some(retry_after) = lockout {
+            set_retry_after(response, retry_after);
+        }
+
+        let state = match *request
+            .local_cache(RecordedRateLimit::default)
+            .0
+            .lock()
+            .unwrap()
+        {
+            Some(state) => state,
+            None => return,
+        };
+
+        response.set_header(Header::new("X-RateLimit-Limit", state.limit.to_string()));
+        response.set_header(Header::new(
+            "X-RateLimit-Remaining",
+            state.remaining.to_string(),
+        ));
+        response.set_header(Header::new(
+            "X-RateLimit-Reset",
+            state.reset_after.as_secs_f64().ceil().to_string(),
+        ));
+
+        // A lockout outlasts the rate limit that applied on the way in
+        if let (Some(retry_after), None) = (state.retry_after, lockout) {
+            set_retry_after(response, retry_after);
+        }
+    }
+}
+
+fn set_retry_after(response: &mut Response<'_>, retry_after: Duration) {
+    response.set_header(Header::new(
+        "Retry-After",
+        retry_after.as_secs_f64().ceil().to_string(),
+    ));
+}
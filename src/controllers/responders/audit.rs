@@ -0,0 +1,13 @@
+use rocket::{
+    response::{Responder, Result as RocketResult},
+    serde::json::Json,
+    Request,
+};
+
+use super::super::types::response::audit::AuditEntries;
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for AuditEntries {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
@@ -0,0 +1,19 @@
+use rocket::{
+    response::{Responder, Result as RocketResult},
+    serde::json::Json,
+    Request,
+};
+
+use super::super::types::response::domain::{DomainRule, DomainRules};
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for DomainRule {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for DomainRules {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
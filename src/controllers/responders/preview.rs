@@ -0,0 +1,75 @@
+use std::io::Cursor;
+
+use rocket::{
+    http::ContentType,
+    response::{Responder, Response, Result as RocketResult},
+    serde::json::Json,
+    Request,
+};
+
+use super::super::types::response::{
+    preview::{UrlPreview, UrlPreviewPage},
+    url::UrlWindowState,
+};
+use super::html::escape_html;
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for UrlPreview {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
+
+impl UrlPreviewPage {
+    fn render(&self) -> String {
+        let preview = &self.0;
+
+        let state = match preview.window_state {
+            UrlWindowState::Pending => "<p>This link isn't active yet.</p>",
+            UrlWindowState::Active => "",
+            UrlWindowState::Expired => "<p>This link has expired.</p>",
+        };
+
+        let url = escape_html(&preview.url);
+
+        return format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="robots" content="noindex">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Link preview</title>
+</head>
+<body>
+<h1>/{}</h1>
+<p>This link leads to <a href="{}" rel="noopener noreferrer nofollow">{}</a></p>
+{}
+<dl>
+<dt>Created by</dt><dd>{}</dd>
+<dt>Created at</dt><dd>{}</dd>
+<dt>Clicks</dt><dd>{}</dd>
+</dl>
+</body>
+</html>
+"#,
+            escape_html(&preview.key),
+            url,
+            url,
+            state,
+            escape_html(&preview.owner),
+            preview.created_at.to_rfc3339(),
+            preview.clicks
+        );
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for UrlPreviewPage {
+    fn respond_to(self, _request: &'r Request<'_>) -> RocketResult<'o> {
+        let body = self.render();
+
+        return Response::build()
+            .header(ContentType::HTML)
+            .sized_body(body.len(), Cursor::new(body))
+            .ok();
+    }
+}
@@ -0,0 +1,19 @@
+use std::io::Cursor;
+
+use rocket::{
+    http::Header,
+    response::{Responder, Response, Result as RocketResult},
+    Request,
+};
+
+use super::super::types::response::qr::QrCode;
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for QrCode {
+    fn respond_to(self, _request: &'r Request<'_>) -> RocketResult<'o> {
+        return Response::build()
+            .header(self.content_type)
+            .header(Header::new("Cache-Control", "private, max-age=3600"))
+            .sized_body(self.bytes.len(), Cursor::new(self.bytes))
+            .ok();
+    }
+}
@@ -0,0 +1,25 @@
+use rocket::{
+    response::{Responder, Result as RocketResult},
+    serde::json::Json,
+    Request,
+};
+
+use super::super::types::response::token::{CreatedToken, Token, Tokens};
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for Token {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for Tokens {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for CreatedToken {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
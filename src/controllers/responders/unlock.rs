@@ -0,0 +1,63 @@
+use std::io::Cursor;
+
+use rocket::{
+    http::{ContentType, Header},
+    response::{Responder, Response, Result as RocketResult},
+    Request,
+};
+
+use super::super::types::response::unlock::UnlockPage;
+use super::html::escape_html;
+
+impl UnlockPage {
+    fn render(&self) -> String {
+        let error = match &self.error {
+            Some(error) => format!("<p class=\"error\">{}</p>", escape_html(error)),
+            None => String::new(),
+        };
+
+        return format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="robots" content="noindex">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Protected link</title>
+</head>
+<body>
+<h1>This link is protected</h1>
+<p>Enter the passphrase to continue.</p>
+{}
+<form method="post" action="{}">
+<input type="password" name="passphrase" autocomplete="off" required autofocus>
+<button type="submit">Continue</button>
+</form>
+</body>
+</html>
+"#,
+            error,
+            escape_html(&self.action)
+        );
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for UnlockPage {
+    fn respond_to(self, _request: &'r Request<'_>) -> RocketResult<'o> {
+        let body = self.render();
+
+        let mut response = Response::build();
+        response
+            .status(self.status)
+            .header(ContentType::HTML)
+            .header(Header::new("Cache-Control", "no-store"))
+            .header(Header::new("X-Robots-Tag", "noindex"))
+            .sized_body(body.len(), Cursor::new(body));
+
+        if let Some(retry_after) = self.retry_after {
+            response.header(Header::new("Retry-After", retry_after.to_string()));
+        }
+
+        return response.ok();
+    }
+}
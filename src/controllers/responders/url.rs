@@ -4,7 +4,9 @@ use rocket::{
     Request,
 };
 
-use super::super::types::response::url::{Url, Urls};
+use super::super::types::response::url::{
+    ImportReport, TransferredUrls, Url, UrlCollaborators, Urls,
+};
 
 impl<'r, 'o: 'r> Responder<'r, 'o> for Url {
     fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
@@ -17,3 +19,21 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for Urls {
         return Json::from(self).respond_to(request);
     }
 }
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for UrlCollaborators {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for TransferredUrls {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for ImportReport {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
@@ -4,7 +4,7 @@ use rocket::{
     Request,
 };
 
-use super::super::types::response::user::{User, Users};
+use super::super::types::response::user::{DeletedUsers, RestoredUser, User, UserLockout, Users};
 
 impl<'r, 'o: 'r> Responder<'r, 'o> for User {
     fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
@@ -17,3 +17,21 @@ impl<'r, 'o: 'r> Responder<'r, 'o> for Users {
         return Json::from(self).respond_to(request);
     }
 }
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for RestoredUser {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for DeletedUsers {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for UserLockout {
+    fn respond_to(self, request: &'r Request<'_>) -> RocketResult<'o> {
+        return Json::from(self).respond_to(request);
+    }
+}
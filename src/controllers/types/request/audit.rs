@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use rocket::form::{self, FromForm, FromFormField, ValueField};
+
+use crate::services::types::{audit::AuditQuery, page::PageRequest};
+
+/// An RFC 3339 timestamp in a query string.
+#[derive(Debug)]
+pub struct Timestamp(pub DateTime<Utc>);
+
+impl<'v> FromFormField<'v> for Timestamp {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        return DateTime::parse_from_rfc3339(field.value)
+            .map(|timestamp| Self(timestamp.with_timezone(&Utc)))
+            .map_err(|_| form::Error::validation("must be an RFC 3339 timestamp").into());
+    }
+}
+
+#[derive(Debug, FromForm)]
+pub struct AuditListQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub from: Option<Timestamp>,
+    pub to: Option<Timestamp>,
+}
+
+impl Into<AuditQuery> for AuditListQuery {
+    fn into(self) -> AuditQuery {
+        return AuditQuery {
+            page: PageRequest {
+                limit: self.limit,
+                cursor: self.cursor,
+            },
+            actor_id: self.actor_id,
+            action: self.action,
+            target_type: self.target_type,
+            target_id: self.target_id,
+            from: self.from.map(|from| from.0),
+            to: self.to.map(|to| to.0),
+        };
+    }
+}
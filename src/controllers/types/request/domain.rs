@@ -0,0 +1,48 @@
+use rocket::{
+    form::FromFormField,
+    serde::{json::Json, Deserialize},
+};
+
+use crate::services::types::domain::{
+    CreateDomainRuleRequest, DomainRuleKind as ServiceDomainRuleKind,
+};
+
+#[derive(Debug, Deserialize, FromFormField)]
+#[serde(rename_all = "camelCase")]
+pub enum DomainRuleKind {
+    #[field(value = "allow")]
+    Allow,
+    #[field(value = "deny")]
+    Deny,
+}
+
+impl Into<ServiceDomainRuleKind> for DomainRuleKind {
+    fn into(self) -> ServiceDomainRuleKind {
+        return match self {
+            Self::Allow => ServiceDomainRuleKind::Allow,
+            Self::Deny => ServiceDomainRuleKind::Deny,
+        };
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDomainRule {
+    pub pattern: String,
+    pub kind: DomainRuleKind,
+}
+
+impl From<Json<CreateDomainRule>> for CreateDomainRule {
+    fn from(json: Json<CreateDomainRule>) -> Self {
+        return json.0;
+    }
+}
+
+impl Into<CreateDomainRuleRequest> for CreateDomainRule {
+    fn into(self) -> CreateDomainRuleRequest {
+        return CreateDomainRuleRequest {
+            pattern: self.pattern,
+            kind: self.kind.into(),
+        };
+    }
+}
@@ -1,2 +1,6 @@
+pub mod audit;
+pub mod domain;
+pub mod page;
+pub mod token;
 pub mod url;
 pub mod user;
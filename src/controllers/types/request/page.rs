@@ -0,0 +1,35 @@
+use rocket::form::{FromForm, FromFormField};
+
+use crate::services::types::page::{PageRequest, SortOrder as ServiceSortOrder};
+
+#[derive(Debug, FromForm)]
+pub struct PageQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+impl Into<PageRequest> for PageQuery {
+    fn into(self) -> PageRequest {
+        return PageRequest {
+            limit: self.limit,
+            cursor: self.cursor,
+        };
+    }
+}
+
+#[derive(Debug, FromFormField)]
+pub enum SortOrder {
+    #[field(value = "asc")]
+    Asc,
+    #[field(value = "desc")]
+    Desc,
+}
+
+impl Into<ServiceSortOrder> for SortOrder {
+    fn into(self) -> ServiceSortOrder {
+        return match self {
+            Self::Asc => ServiceSortOrder::Asc,
+            Self::Desc => ServiceSortOrder::Desc,
+        };
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::{json::Json, Deserialize};
+
+use crate::services::types::token::{CreateTokenRequest, Scope as ServiceScope};
+
+#[derive(Debug, Deserialize)]
+pub enum Scope {
+    #[serde(rename = "urls:read")]
+    UrlsRead,
+    #[serde(rename = "urls:write")]
+    UrlsWrite,
+    #[serde(rename = "users:admin")]
+    UsersAdmin,
+}
+
+impl Into<ServiceScope> for Scope {
+    fn into(self) -> ServiceScope {
+        return match self {
+            Self::UrlsRead => ServiceScope::UrlsRead,
+            Self::UrlsWrite => ServiceScope::UrlsWrite,
+            Self::UsersAdmin => ServiceScope::UsersAdmin,
+        };
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateToken {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<Json<CreateToken>> for CreateToken {
+    fn from(json: Json<CreateToken>) -> Self {
+        return json.0;
+    }
+}
+
+impl Into<CreateTokenRequest> for CreateToken {
+    fn into(self) -> CreateTokenRequest {
+        return CreateTokenRequest {
+            name: self.name,
+            scopes: self.scopes.into_iter().map(Into::into).collect(),
+            expires_at: self.expires_at,
+        };
+    }
+}
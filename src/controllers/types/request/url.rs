@@ -1,12 +1,45 @@
-use rocket::serde::{json::Json, Deserialize};
+use std::path::PathBuf;
 
-use crate::services::types::url::{CreateUrlRequest, UpdateUrlRequest};
+use chrono::{DateTime, Utc};
+use rocket::{
+    data::{self, ByteUnit, Data, FromData},
+    form::{FromForm, FromFormField},
+    http::{
+        uri::{error::PathError, fmt::Path, Segments},
+        Status,
+    },
+    outcome::Outcome,
+    request::FromSegments,
+    serde::{
+        json::{serde_json, Json},
+        Deserialize, Deserializer,
+    },
+    Request,
+};
+
+use crate::errors::url::UrlError;
+use crate::services::types::{
+    page::{PageRequest, SortOrder as ServiceSortOrder},
+    qr::{QrErrorCorrection as ServiceQrErrorCorrection, QrFormat as ServiceQrFormat, QrOptions},
+    url::{
+        CreateUrlRequest, ImportMode as ServiceImportMode, ImportUrlRequest, UnreadableImportRow,
+        UpdateUrlRequest, UrlQuery, UrlSort as ServiceUrlSort,
+    },
+};
+
+use super::page::SortOrder;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateUrl {
-    pub key: String,
+    pub key: Option<String>,
     pub url: String,
+    pub activates_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: Option<u16>,
+    pub is_prefix: Option<bool>,
+    pub passphrase: Option<String>,
+    pub preview_enabled: Option<bool>,
 }
 
 impl From<Json<CreateUrl>> for CreateUrl {
@@ -20,6 +53,12 @@ impl Into<CreateUrlRequest> for CreateUrl {
         return CreateUrlRequest {
             key: self.key,
             url: self.url,
+            activates_at: self.activates_at,
+            expires_at: self.expires_at,
+            redirect_type: self.redirect_type,
+            is_prefix: self.is_prefix,
+            passphrase: self.passphrase,
+            preview_enabled: self.preview_enabled,
         };
     }
 }
@@ -29,6 +68,23 @@ impl Into<CreateUrlRequest> for CreateUrl {
 pub struct UpdateUrl {
     pub key: Option<String>,
     pub url: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub activates_at: Option<Option<DateTime<Utc>>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub expires_at: Option<Option<DateTime<Utc>>>,
+    pub redirect_type: Option<u16>,
+    pub is_prefix: Option<bool>,
+    pub passphrase: Option<String>,
+    pub preview_enabled: Option<bool>,
+}
+
+/// Tells an explicit `null` (`Some(None)`) apart from a missing field (`None`).
+fn deserialize_nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    return Option::<T>::deserialize(deserializer).map(Some);
 }
 
 impl From<Json<UpdateUrl>> for UpdateUrl {
@@ -42,6 +98,420 @@ impl Into<UpdateUrlRequest> for UpdateUrl {
         return UpdateUrlRequest {
             key: self.key,
             url: self.url,
+            activates_at: self.activates_at,
+            expires_at: self.expires_at,
+            redirect_type: self.redirect_type,
+            is_prefix: self.is_prefix,
+            passphrase: self.passphrase,
+            preview_enabled: self.preview_enabled,
+        };
+    }
+}
+
+/// Form posted from the unlock page of a passphrase-protected URL.
+#[derive(Debug, FromForm)]
+pub struct UnlockUrl {
+    pub passphrase: String,
+}
+
+/// Path segments of the form `<key..>/stats`, resolving to the URL key.
+#[derive(Debug)]
+pub struct UrlStatsKey(pub String);
+
+impl<'r> FromSegments<'r> for UrlStatsKey {
+    type Error = Option<PathError>;
+
+    fn from_segments(segments: Segments<'r, Path>) -> Result<Self, Self::Error> {
+        return key_with_suffix(segments, "stats").map(Self);
+    }
+}
+
+/// Path segments of the form `<key..>/qr`, resolving to the URL key.
+#[derive(Debug)]
+pub struct UrlQrKey(pub String);
+
+impl<'r> FromSegments<'r> for UrlQrKey {
+    type Error = Option<PathError>;
+
+    fn from_segments(segments: Segments<'r, Path>) -> Result<Self, Self::Error> {
+        return key_with_suffix(segments, "qr").map(Self);
+    }
+}
+
+/// Path segments of the form `<key..>/transfer`, resolving to the URL key.
+#[derive(Debug)]
+pub struct UrlTransferKey(pub String);
+
+impl<'r> FromSegments<'r> for UrlTransferKey {
+    type Error = Option<PathError>;
+
+    fn from_segments(segments: Segments<'r, Path>) -> Result<Self, Self::Error> {
+        return key_with_suffix(segments, "transfer").map(Self);
+    }
+}
+
+/// Path segments of the form `<key..>/collaborators`, resolving to the URL key.
+#[derive(Debug)]
+pub struct UrlCollaboratorsKey(pub String);
+
+impl<'r> FromSegments<'r> for UrlCollaboratorsKey {
+    type Error = Option<PathError>;
+
+    fn from_segments(segments: Segments<'r, Path>) -> Result<Self, Self::Error> {
+        return key_with_suffix(segments, "collaborators").map(Self);
+    }
+}
+
+/// Path segments of the form `<key..>/collaborators/<user_id>`.
+#[derive(Debug)]
+pub struct UrlCollaboratorPath {
+    pub key: String,
+    pub user_id: i32,
+}
+
+impl<'r> FromSegments<'r> for UrlCollaboratorPath {
+    type Error = Option<PathError>;
+
+    fn from_segments(segments: Segments<'r, Path>) -> Result<Self, Self::Error> {
+        let path: PathBuf = segments.to_path_buf(false).map_err(Some)?;
+
+        let user_id = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse().ok())
+            .ok_or(None)?;
+
+        let key = match path.parent() {
+            Some(parent)
+                if parent
+                    .file_name()
+                    .is_some_and(|name| name == "collaborators") =>
+            {
+                parent.parent()
+            }
+            _ => None,
+        };
+
+        return match key {
+            Some(key) if !key.as_os_str().is_empty() => Ok(Self {
+                key: key.display().to_string(),
+                user_id,
+            }),
+            _ => Err(None),
         };
     }
 }
+
+/// The user that URLs are transferred to.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlTransfer {
+    pub user_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddUrlCollaborator {
+    pub user_id: i32,
+}
+
+/// Path segments of the form `<key..>/restore`, resolving to the URL key.
+#[derive(Debug)]
+pub struct UrlRestoreKey(pub String);
+
+impl<'r> FromSegments<'r> for UrlRestoreKey {
+    type Error = Option<PathError>;
+
+    fn from_segments(segments: Segments<'r, Path>) -> Result<Self, Self::Error> {
+        return key_with_suffix(segments, "restore").map(Self);
+    }
+}
+
+fn key_with_suffix(
+    segments: Segments<'_, Path>,
+    suffix: &str,
+) -> Result<String, Option<PathError>> {
+    let path: PathBuf = segments.to_path_buf(false).map_err(Some)?;
+
+    return match (path.parent(), path.file_name()) {
+        (Some(key), Some(name)) if name == suffix && !key.as_os_str().is_empty() => {
+            Ok(key.display().to_string())
+        }
+        _ => Err(None),
+    };
+}
+
+#[derive(Debug, FromFormField)]
+pub enum QrFormat {
+    #[field(value = "png")]
+    Png,
+    #[field(value = "svg")]
+    Svg,
+}
+
+impl Into<ServiceQrFormat> for QrFormat {
+    fn into(self) -> ServiceQrFormat {
+        return match self {
+            Self::Png => ServiceQrFormat::Png,
+            Self::Svg => ServiceQrFormat::Svg,
+        };
+    }
+}
+
+/// Error correction level, matched case-insensitively.
+#[derive(Debug, FromFormField)]
+pub enum QrErrorCorrection {
+    #[field(value = "L")]
+    Low,
+    #[field(value = "M")]
+    Medium,
+    #[field(value = "Q")]
+    Quartile,
+    #[field(value = "H")]
+    High,
+}
+
+impl Into<ServiceQrErrorCorrection> for QrErrorCorrection {
+    fn into(self) -> ServiceQrErrorCorrection {
+        return match self {
+            Self::Low => ServiceQrErrorCorrection::Low,
+            Self::Medium => ServiceQrErrorCorrection::Medium,
+            Self::Quartile => ServiceQrErrorCorrection::Quartile,
+            Self::High => ServiceQrErrorCorrection::High,
+        };
+    }
+}
+
+#[derive(Debug, FromForm)]
+pub struct QrQuery {
+    pub format: Option<QrFormat>,
+    pub size: Option<u32>,
+    pub ec: Option<QrErrorCorrection>,
+    pub margin: Option<u32>,
+}
+
+impl Into<QrOptions> for QrQuery {
+    fn into(self) -> QrOptions {
+        let defaults = QrOptions::default();
+
+        return QrOptions {
+            format: self.format.map(Into::into).unwrap_or(defaults.format),
+            size: self.size.unwrap_or(defaults.size),
+            error_correction: self.ec.map(Into::into).unwrap_or(defaults.error_correction),
+            margin: self.margin.unwrap_or(defaults.margin),
+        };
+    }
+}
+
+#[derive(Debug, FromFormField)]
+pub enum UrlSort {
+    #[field(value = "key")]
+    Key,
+    #[field(value = "url")]
+    Url,
+    #[field(value = "userId")]
+    UserId,
+    #[field(value = "activatesAt")]
+    ActivatesAt,
+    #[field(value = "expiresAt")]
+    ExpiresAt,
+}
+
+impl Into<ServiceUrlSort> for UrlSort {
+    fn into(self) -> ServiceUrlSort {
+        return match self {
+            Self::Key => ServiceUrlSort::Key,
+            Self::Url => ServiceUrlSort::Url,
+            Self::UserId => ServiceUrlSort::UserId,
+            Self::ActivatesAt => ServiceUrlSort::ActivatesAt,
+            Self::ExpiresAt => ServiceUrlSort::ExpiresAt,
+        };
+    }
+}
+
+#[derive(Debug, FromForm)]
+pub struct UrlListQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<UrlSort>,
+    pub order: Option<SortOrder>,
+    pub prefix: Option<String>,
+    pub host: Option<String>,
+    pub user_id: Option<i32>,
+}
+
+impl Into<UrlQuery> for UrlListQuery {
+    fn into(self) -> UrlQuery {
+        return UrlQuery {
+            page: PageRequest {
+                limit: self.limit,
+                cursor: self.cursor,
+            },
+            sort: self.sort.map(Into::into).unwrap_or(ServiceUrlSort::Key),
+            order: self.order.map(Into::into).unwrap_or(ServiceSortOrder::Asc),
+            key_prefix: self.prefix,
+            host: self.host,
+            user_id: self.user_id,
+            after_key: None,
+        };
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportUrl {
+    pub key: String,
+    pub url: String,
+    pub activates_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: Option<u16>,
+    pub is_prefix: Option<bool>,
+}
+
+impl Into<ImportUrlRequest> for ImportUrl {
+    fn into(self) -> ImportUrlRequest {
+        return ImportUrlRequest {
+            key: self.key,
+            url: self.url,
+            activates_at: self.activates_at,
+            expires_at: self.expires_at,
+            redirect_type: self.redirect_type,
+            is_prefix: self.is_prefix,
+        };
+    }
+}
+
+/// Request body of an import: a JSON array of URLs, or a CSV file with a
+/// `key,url[,activatesAt,expiresAt,redirectType,isPrefix]` header row when
+/// sent as `text/csv`. Rows that can't be read are kept, so that they're
+/// reported along with the others.
+#[derive(Debug)]
+pub struct UrlImport(pub Vec<Result<ImportUrl, UnreadableImportRow>>);
+
+impl UrlImport {
+    /// Default body size limit, overridable with the `import` data limit.
+    const LIMIT: ByteUnit = ByteUnit::Mebibyte(8);
+
+    fn parse_csv(bytes: &[u8]) -> Result<Vec<Result<ImportUrl, UnreadableImportRow>>, UrlError> {
+        // Rows with missing columns fail on their own instead of the whole file
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .from_reader(bytes);
+
+        let headers = reader
+            .headers()
+            .map_err(|e| UrlError::ImportInvalid(e.to_string()))?
+            .clone();
+        let key_column = headers.iter().position(|header| header == "key");
+
+        return Ok(reader
+            .records()
+            .map(|record| {
+                let record = record.map_err(|e| UnreadableImportRow {
+                    key: String::new(),
+                    error: e.to_string(),
+                })?;
+
+                return record
+                    .deserialize(Some(&headers))
+                    .map_err(|e| UnreadableImportRow {
+                        key: String::from(key_column.and_then(|i| record.get(i)).unwrap_or("")),
+                        error: e.to_string(),
+                    });
+            })
+            .collect());
+    }
+
+    fn parse_json(bytes: &[u8]) -> Result<Vec<Result<ImportUrl, UnreadableImportRow>>, UrlError> {
+        let values: Vec<serde_json::Value> =
+            serde_json::from_slice(bytes).map_err(|e| UrlError::ImportInvalid(e.to_string()))?;
+
+        return Ok(values
+            .into_iter()
+            .map(|value| {
+                let key = String::from(value.get("key").and_then(|key| key.as_str()).unwrap_or(""));
+
+                return serde_json::from_value(value).map_err(|e| UnreadableImportRow {
+                    key,
+                    error: e.to_string(),
+                });
+            })
+            .collect());
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for UrlImport {
+    type Error = UrlError;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let is_csv = match req.content_type() {
+            Some(content_type) if content_type.is_csv() => true,
+            Some(content_type) if content_type.is_json() => false,
+            None => false,
+            Some(_) => {
+                return Outcome::Failure((
+                    Status::UnsupportedMediaType,
+                    UrlError::ImportInvalid(String::from(
+                        "Expected a text/csv or application/json body",
+                    )),
+                ))
+            }
+        };
+
+        let limit = req.limits().get("import").unwrap_or(Self::LIMIT);
+
+        let bytes = match data.open(limit).into_bytes().await {
+            Ok(bytes) if bytes.is_complete() => bytes.into_inner(),
+            Ok(_) => {
+                return Outcome::Failure((
+                    Status::PayloadTooLarge,
+                    UrlError::ImportInvalid(format!("Body exceeds the {} limit", limit)),
+                ))
+            }
+            Err(e) => {
+                return Outcome::Failure((
+                    Status::BadRequest,
+                    UrlError::ImportInvalid(e.to_string()),
+                ))
+            }
+        };
+
+        let urls = if is_csv {
+            Self::parse_csv(&bytes)
+        } else {
+            Self::parse_json(&bytes)
+        };
+
+        return match urls {
+            Ok(urls) => Outcome::Success(Self(urls)),
+            Err(e) => Outcome::Failure((Status::BadRequest, e)),
+        };
+    }
+}
+
+#[derive(Debug, FromFormField)]
+pub enum ImportMode {
+    #[field(value = "allOrNothing")]
+    AllOrNothing,
+    #[field(value = "bestEffort")]
+    BestEffort,
+}
+
+impl Into<ServiceImportMode> for ImportMode {
+    fn into(self) -> ServiceImportMode {
+        return match self {
+            Self::AllOrNothing => ServiceImportMode::AllOrNothing,
+            Self::BestEffort => ServiceImportMode::BestEffort,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, FromFormField)]
+pub enum ExportFormat {
+    #[field(value = "csv")]
+    Csv,
+    #[field(value = "json")]
+    Json,
+}
@@ -1,6 +1,14 @@
-use rocket::serde::{json::Json, Deserialize};
+use rocket::{
+    form::{FromForm, FromFormField},
+    serde::{json::Json, Deserialize},
+};
 
-use crate::services::types::user::{CreateUserRequest, UpdateUserRequest};
+use crate::services::types::{
+    page::{PageRequest, SortOrder as ServiceSortOrder},
+    user::{CreateUserRequest, UpdateUserRequest, UserQuery, UserSort as ServiceUserSort},
+};
+
+use super::page::SortOrder;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -61,3 +69,43 @@ impl From<Json<UpdateUserClientSecret>> for UpdateUserClientSecret {
         return json.0;
     }
 }
+
+#[derive(Debug, FromFormField)]
+pub enum UserSort {
+    #[field(value = "id")]
+    Id,
+    #[field(value = "clientId")]
+    ClientId,
+}
+
+impl Into<ServiceUserSort> for UserSort {
+    fn into(self) -> ServiceUserSort {
+        return match self {
+            Self::Id => ServiceUserSort::Id,
+            Self::ClientId => ServiceUserSort::ClientId,
+        };
+    }
+}
+
+#[derive(Debug, FromForm)]
+pub struct UserListQuery {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub sort: Option<UserSort>,
+    pub order: Option<SortOrder>,
+    pub client_id: Option<String>,
+}
+
+impl Into<UserQuery> for UserListQuery {
+    fn into(self) -> UserQuery {
+        return UserQuery {
+            page: PageRequest {
+                limit: self.limit,
+                cursor: self.cursor,
+            },
+            sort: self.sort.map(Into::into).unwrap_or(ServiceUserSort::Id),
+            order: self.order.map(Into::into).unwrap_or(ServiceSortOrder::Asc),
+            client_id: self.client_id,
+        };
+    }
+}
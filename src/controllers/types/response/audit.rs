@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::{json::Value, Serialize};
+
+use crate::services::types::{audit::AuditEntry as ServiceAuditEntry, page::Page};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    pub actor_id: Option<i32>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntries {
+    pub values: Vec<AuditEntry>,
+    pub next: Option<String>,
+    pub total: i64,
+}
+
+impl From<ServiceAuditEntry> for AuditEntry {
+    fn from(entry: ServiceAuditEntry) -> Self {
+        return Self {
+            id: entry.id,
+            actor_id: entry.actor_id,
+            action: entry.action,
+            target_type: entry.target_type,
+            target_id: entry.target_id,
+            before: entry.before,
+            after: entry.after,
+            created_at: entry.created_at,
+        };
+    }
+}
+
+impl From<Page<ServiceAuditEntry>> for AuditEntries {
+    fn from(page: Page<ServiceAuditEntry>) -> Self {
+        return Self {
+            values: page.values.into_iter().map(AuditEntry::from).collect(),
+            next: page.next,
+            total: page.total,
+        };
+    }
+}
@@ -0,0 +1,43 @@
+use rocket::serde::Serialize;
+
+use crate::services::types::click::{
+    ClickStats as ServiceClickStats, DailyClicks as ServiceDailyClicks,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyClicks {
+    pub date: String,
+    pub clicks: i64,
+    pub unique_visitors: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClickStats {
+    pub key: String,
+    pub total_clicks: i64,
+    pub unique_visitors: i64,
+    pub days: Vec<DailyClicks>,
+}
+
+impl From<ServiceDailyClicks> for DailyClicks {
+    fn from(day: ServiceDailyClicks) -> Self {
+        return Self {
+            date: day.date,
+            clicks: day.clicks,
+            unique_visitors: day.unique_visitors,
+        };
+    }
+}
+
+impl From<ServiceClickStats> for ClickStats {
+    fn from(stats: ServiceClickStats) -> Self {
+        return Self {
+            key: stats.key,
+            total_clicks: stats.total_clicks,
+            unique_visitors: stats.unique_visitors,
+            days: stats.days.into_iter().map(DailyClicks::from).collect(),
+        };
+    }
+}
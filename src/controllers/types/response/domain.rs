@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::Serialize;
+
+use crate::services::types::domain::DomainRule as ServiceDomainRule;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainRule {
+    pub id: i32,
+    pub pattern: String,
+    pub kind: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainRules {
+    pub values: Vec<DomainRule>,
+}
+
+impl From<ServiceDomainRule> for DomainRule {
+    fn from(rule: ServiceDomainRule) -> Self {
+        return Self {
+            id: rule.id,
+            pattern: rule.pattern,
+            kind: String::from(rule.kind.as_str()),
+            created_at: rule.created_at,
+        };
+    }
+}
+
+impl From<Vec<ServiceDomainRule>> for DomainRules {
+    fn from(rules: Vec<ServiceDomainRule>) -> Self {
+        return Self {
+            values: rules.into_iter().map(DomainRule::from).collect(),
+        };
+    }
+}
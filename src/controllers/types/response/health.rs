@@ -0,0 +1,30 @@
+use rocket::serde::Serialize;
+
+use crate::services::types::cache::RedirectCacheStats as ServiceRedirectCacheStats;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedirectCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub size: usize,
+    pub capacity: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Health {
+    pub status: String,
+    pub redirect_cache: Option<RedirectCacheStats>,
+}
+
+impl From<ServiceRedirectCacheStats> for RedirectCacheStats {
+    fn from(stats: ServiceRedirectCacheStats) -> Self {
+        return Self {
+            hits: stats.hits,
+            misses: stats.misses,
+            size: stats.size,
+            capacity: stats.capacity,
+        };
+    }
+}
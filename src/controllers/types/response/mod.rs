@@ -1,2 +1,10 @@
+pub mod audit;
+pub mod click;
+pub mod domain;
+pub mod health;
+pub mod preview;
+pub mod qr;
+pub mod token;
+pub mod unlock;
 pub mod url;
 pub mod user;
@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::Serialize;
+
+use super::url::UrlWindowState;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlPreview {
+    pub key: String,
+    pub url: String,
+    pub owner: String,
+    pub created_at: DateTime<Utc>,
+    pub clicks: i64,
+    pub window_state: UrlWindowState,
+}
+
+/// The same preview rendered as an HTML page for visitors.
+#[derive(Debug)]
+pub struct UrlPreviewPage(pub UrlPreview);
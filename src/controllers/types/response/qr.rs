@@ -0,0 +1,23 @@
+use rocket::http::ContentType;
+
+use crate::services::types::qr::{QrFormat, QrImage};
+
+#[derive(Debug)]
+pub struct QrCode {
+    pub content_type: ContentType,
+    pub bytes: Vec<u8>,
+}
+
+impl From<QrImage> for QrCode {
+    fn from(image: QrImage) -> Self {
+        let content_type = match image.format {
+            QrFormat::Png => ContentType::PNG,
+            QrFormat::Svg => ContentType::SVG,
+        };
+
+        return Self {
+            content_type,
+            bytes: image.bytes,
+        };
+    }
+}
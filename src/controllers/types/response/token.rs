@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::Serialize;
+
+use crate::services::types::token::{CreatedToken as ServiceCreatedToken, Token as ServiceToken};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Token {
+    pub id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tokens {
+    pub values: Vec<Token>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedToken {
+    /// The bearer token, only ever returned when it is created
+    pub token: String,
+    #[serde(flatten)]
+    pub details: Token,
+}
+
+impl From<ServiceToken> for Token {
+    fn from(token: ServiceToken) -> Self {
+        return Self {
+            id: token.id,
+            name: token.name,
+            prefix: token.prefix,
+            scopes: token
+                .scopes
+                .iter()
+                .map(|scope| String::from(scope.as_str()))
+                .collect(),
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+        };
+    }
+}
+
+impl From<Vec<ServiceToken>> for Tokens {
+    fn from(tokens: Vec<ServiceToken>) -> Self {
+        return Self {
+            values: tokens.into_iter().map(Token::from).collect(),
+        };
+    }
+}
+
+impl From<ServiceCreatedToken> for CreatedToken {
+    fn from(created: ServiceCreatedToken) -> Self {
+        return Self {
+            token: created.secret,
+            details: Token::from(created.token),
+        };
+    }
+}
@@ -0,0 +1,10 @@
+use rocket::http::Status;
+
+/// HTML form asking for the passphrase of a protected URL.
+#[derive(Debug)]
+pub struct UnlockPage {
+    pub status: Status,
+    pub action: String,
+    pub error: Option<String>,
+    pub retry_after: Option<u64>,
+}
@@ -1,6 +1,21 @@
-use rocket::serde::Serialize;
+use chrono::{DateTime, Utc};
+use rocket::serde::{json::serde_json, Serialize};
 
-use crate::services::types::url::Url as ServiceUrl;
+use crate::errors::url::UrlError;
+use crate::services::types::page::Page;
+use crate::services::types::url::{
+    ImportReport as ServiceImportReport, ImportRowResult as ServiceImportRowResult,
+    ImportRowStatus as ServiceImportRowStatus, Url as ServiceUrl,
+    UrlCollaborator as ServiceUrlCollaborator, UrlWindowState as ServiceUrlWindowState,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UrlWindowState {
+    Pending,
+    Active,
+    Expired,
+}
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -8,26 +23,219 @@ pub struct Url {
     pub key: String,
     pub url: String,
     pub user_id: i32,
+    pub activates_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: Option<u16>,
+    pub is_prefix: bool,
+    pub is_protected: bool,
+    pub preview_enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub window_state: UrlWindowState,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Urls {
     pub values: Vec<Url>,
+    pub next: Option<String>,
+    pub total: i64,
 }
 
-impl From<Vec<Url>> for Urls {
-    fn from(values: Vec<Url>) -> Self {
-        return Self { values };
+impl From<Page<ServiceUrl>> for Urls {
+    fn from(page: Page<ServiceUrl>) -> Self {
+        return Self {
+            values: page.values.into_iter().map(Url::from).collect(),
+            next: page.next,
+            total: page.total,
+        };
+    }
+}
+
+impl From<ServiceUrlWindowState> for UrlWindowState {
+    fn from(state: ServiceUrlWindowState) -> Self {
+        return match state {
+            ServiceUrlWindowState::Pending => Self::Pending,
+            ServiceUrlWindowState::Active => Self::Active,
+            ServiceUrlWindowState::Expired => Self::Expired,
+        };
     }
 }
 
 impl From<ServiceUrl> for Url {
     fn from(url: ServiceUrl) -> Self {
+        let window_state = UrlWindowState::from(url.window_state());
+
         return Self {
             key: url.key,
             url: url.url,
             user_id: url.user_id,
+            activates_at: url.activates_at,
+            expires_at: url.expires_at,
+            redirect_type: url.redirect_type.map(|redirect_type| redirect_type.code()),
+            is_prefix: url.is_prefix,
+            is_protected: url.passphrase_hash.is_some(),
+            preview_enabled: url.preview_enabled,
+            created_at: url.created_at,
+            deleted_at: url.deleted_at,
+            window_state,
+        };
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlCollaborator {
+    pub user_id: i32,
+    pub client_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlCollaborators {
+    pub values: Vec<UrlCollaborator>,
+}
+
+impl From<ServiceUrlCollaborator> for UrlCollaborator {
+    fn from(collaborator: ServiceUrlCollaborator) -> Self {
+        return Self {
+            user_id: collaborator.user_id,
+            client_id: collaborator.client_id,
+            created_at: collaborator.created_at,
+        };
+    }
+}
+
+impl From<Vec<ServiceUrlCollaborator>> for UrlCollaborators {
+    fn from(collaborators: Vec<ServiceUrlCollaborator>) -> Self {
+        return Self {
+            values: collaborators
+                .into_iter()
+                .map(UrlCollaborator::from)
+                .collect(),
+        };
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferredUrls {
+    pub transferred: u64,
+}
+
+/// A URL as written by an export, in the same shape accepted by an import.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportUrl {
+    pub key: String,
+    pub url: String,
+    pub activates_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: Option<u16>,
+    pub is_prefix: bool,
+}
+
+impl ExportUrl {
+    pub const CSV_HEADER: &'static str = "key,url,activatesAt,expiresAt,redirectType,isPrefix\n";
+
+    pub fn to_csv(&self) -> String {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(vec![]);
+
+        // Writing to memory only fails if the record cannot be serialized
+        if let Err(e) = writer.serialize(self) {
+            error!("Failed to serialize {} as CSV: {}", self.key, e);
+            return String::new();
+        }
+
+        return match writer.into_inner() {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(_) => String::new(),
+        };
+    }
+
+    pub fn to_json(&self) -> String {
+        return serde_json::to_string(self).unwrap_or_default();
+    }
+}
+
+impl From<ServiceUrl> for ExportUrl {
+    fn from(url: ServiceUrl) -> Self {
+        return Self {
+            key: url.key,
+            url: url.url,
+            activates_at: url.activates_at,
+            expires_at: url.expires_at,
+            redirect_type: url.redirect_type.map(|redirect_type| redirect_type.code()),
+            is_prefix: url.is_prefix,
+        };
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportRowStatus {
+    Created,
+    Failed,
+    RolledBack,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRow {
+    pub row: usize,
+    pub key: String,
+    pub status: ImportRowStatus,
+    pub error: Option<UrlError>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub committed: bool,
+    pub created: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRow>,
+}
+
+impl From<ServiceImportRowResult> for ImportRow {
+    fn from(result: ServiceImportRowResult) -> Self {
+        let (status, error) = match result.status {
+            ServiceImportRowStatus::Created => (ImportRowStatus::Created, None),
+            ServiceImportRowStatus::Failed(e) => (ImportRowStatus::Failed, Some(e)),
+            ServiceImportRowStatus::RolledBack => (ImportRowStatus::RolledBack, None),
+        };
+
+        return Self {
+            row: result.row,
+            key: result.key,
+            status,
+            error,
+        };
+    }
+}
+
+impl From<ServiceImportReport> for ImportReport {
+    fn from(report: ServiceImportReport) -> Self {
+        let rows: Vec<ImportRow> = report.rows.into_iter().map(ImportRow::from).collect();
+
+        let created = rows
+            .iter()
+            .filter(|row| matches!(row.status, ImportRowStatus::Created))
+            .count();
+
+        let failed = rows
+            .iter()
+            .filter(|row| matches!(row.status, ImportRowStatus::Failed))
+            .count();
+
+        return Self {
+            committed: report.committed,
+            created,
+            failed,
+            rows,
         };
     }
 }
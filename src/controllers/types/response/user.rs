@@ -1,6 +1,13 @@
+use chrono::{DateTime, Utc};
 use rocket::serde::Serialize;
 
-use crate::services::types::user::User as ServiceUser;
+use crate::errors::url::UrlError;
+use crate::services::types::lockout::UserLockout as ServiceUserLockout;
+use crate::services::types::page::Page;
+use crate::services::types::url::UnrestoredUrl as ServiceUnrestoredUrl;
+use crate::services::types::user::{
+    DeletedUser as ServiceDeletedUser, RestoredUser as ServiceRestoredUser, User as ServiceUser,
+};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -14,11 +21,96 @@ pub struct User {
 #[serde(rename_all = "camelCase")]
 pub struct Users {
     pub values: Vec<User>,
+    pub next: Option<String>,
+    pub total: i64,
 }
 
-impl From<Vec<User>> for Users {
-    fn from(values: Vec<User>) -> Self {
-        return Self { values };
+impl From<Page<ServiceUser>> for Users {
+    fn from(page: Page<ServiceUser>) -> Self {
+        return Self {
+            values: page.values.into_iter().map(User::from).collect(),
+            next: page.next,
+            total: page.total,
+        };
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedUser {
+    pub id: i32,
+    pub client_id: String,
+    pub is_admin: bool,
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnrestoredUrl {
+    pub key: String,
+    pub error: UrlError,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredUser {
+    pub id: i32,
+    pub client_id: String,
+    pub is_admin: bool,
+    /// URLs deleted together with the user that were refused on restore
+    pub unrestored_urls: Vec<UnrestoredUrl>,
+}
+
+impl From<ServiceUnrestoredUrl> for UnrestoredUrl {
+    fn from(unrestored: ServiceUnrestoredUrl) -> Self {
+        return Self {
+            key: unrestored.key,
+            error: unrestored.error,
+        };
+    }
+}
+
+impl From<ServiceRestoredUser> for RestoredUser {
+    fn from(restored: ServiceRestoredUser) -> Self {
+        return Self {
+            id: restored.user.id,
+            client_id: restored.user.client_id,
+            is_admin: restored.user.is_admin,
+            unrestored_urls: restored
+                .unrestored_urls
+                .into_iter()
+                .map(UnrestoredUrl::from)
+                .collect(),
+        };
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedUsers {
+    pub values: Vec<DeletedUser>,
+    pub next: Option<String>,
+    pub total: i64,
+}
+
+impl From<Page<ServiceDeletedUser>> for DeletedUsers {
+    fn from(page: Page<ServiceDeletedUser>) -> Self {
+        return Self {
+            values: page.values.into_iter().map(DeletedUser::from).collect(),
+            next: page.next,
+            total: page.total,
+        };
+    }
+}
+
+impl From<ServiceDeletedUser> for DeletedUser {
+    fn from(deleted: ServiceDeletedUser) -> Self {
+        return Self {
+            id: deleted.user.id,
+            client_id: deleted.user.client_id,
+            is_admin: deleted.user.is_admin,
+            deleted_at: deleted.deleted_at,
+        };
     }
 }
 
@@ -31,3 +123,25 @@ impl From<ServiceUser> for User {
         };
     }
 }
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserLockout {
+    pub user_id: i32,
+    pub client_id: String,
+    pub failures: u32,
+    pub lockouts: u32,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl From<ServiceUserLockout> for UserLockout {
+    fn from(lockout: ServiceUserLockout) -> Self {
+        return Self {
+            user_id: lockout.user_id,
+            client_id: lockout.client_id,
+            failures: lockout.failures,
+            lockouts: lockout.lockouts,
+            locked_until: lockout.locked_until,
+        };
+    }
+}
@@ -0,0 +1,53 @@
+use rocket::{
+    http::Status,
+    response::{Responder, Result},
+    serde::json::Json,
+    Request,
+};
+
+use rocket_sync_db_pools::postgres::Error as PostgresError;
+use serde::Serialize;
+
+use super::database::DatabaseError;
+
+#[derive(Debug, Serialize)]
+pub enum AuditError {
+    CursorInvalid,
+    RangeInvalid,
+    Database(DatabaseError),
+    Unknown,
+}
+
+impl AuditError {
+    fn bad_request<'r, 'o>(self, request: &'r Request<'_>) -> Result<'o> {
+        return self.with_status(request, Status::BadRequest);
+    }
+
+    fn with_status<'r, 'o>(self, request: &'r Request<'_>, status: Status) -> Result<'o> {
+        return Responder::respond_to(Json(self), request).map(|mut res| {
+            res.set_status(status);
+            return res;
+        });
+    }
+}
+
+impl From<PostgresError> for AuditError {
+    fn from(e: PostgresError) -> Self {
+        return Self::Database(DatabaseError::from(e));
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for AuditError {
+    fn respond_to(self, request: &'r Request<'_>) -> Result<'o> {
+        return match self {
+            Self::CursorInvalid | Self::RangeInvalid => self.bad_request(request),
+
+            Self::Database(ref e) => {
+                let status = e.status();
+                self.with_status(request, status)
+            }
+
+            _ => Err(Status::InternalServerError),
+        };
+    }
+}
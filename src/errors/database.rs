@@ -0,0 +1,225 @@
+use rocket::http::Status;
+use rocket_sync_db_pools::postgres::{error::SqlState, Error as PostgresError};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DatabaseErrorKind {
+    Unavailable,
+    UniqueViolation,
+    ForeignKeyViolation,
+    Query,
+}
+
+/// Database failure with the underlying cause reduced to its SQLSTATE code,
+/// so that no query details or row values are leaked to clients.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseError {
+    pub kind: DatabaseErrorKind,
+    pub code: Option<String>,
+    /// Name of the violated constraint, kept out of responses like the rest
+    /// of the schema
+    #[serde(skip)]
+    pub constraint: Option<String>,
+}
+
+impl DatabaseError {
+    /// No connection could be had, whether from the pool or the server.
+    pub fn unavailable() -> DatabaseError {
+        return DatabaseError {
+            kind: DatabaseErrorKind::Unavailable,
+            code: None,
+            constraint: None,
+        };
+    }
+
+    /// Whether this is a unique violation of the named constraint. A
+    /// violation of any other constraint isn't one the caller can explain.
+    pub fn is_unique_violation_of(&self, constraint: &str) -> bool {
+        return matches!(self.kind, DatabaseErrorKind::UniqueViolation)
+            && self.constraint.as_deref() == Some(constraint);
+    }
+
+    pub fn status(&self) -> Status {
+        return match self.kind {
+            DatabaseErrorKind::Unavailable => Status::ServiceUnavailable,
+            _ => Status::InternalServerError,
+        };
+    }
+}
+
+impl From<PostgresError> for DatabaseError {
+    fn from(e: PostgresError) -> Self {
+        error!("Database error: {}", e);
+
+        let code = match e.code() {
+            Some(code) => code,
+            None => {
+                // Errors without a SQLSTATE come from the connection itself
+                return DatabaseError::unavailable();
+            }
+        };
+
+        // Connection exceptions, insufficient resources and operator intervention
+        let is_unavailable = matches!(&code.code()[..2], "08" | "53" | "57");
+
+        let kind = if e.is_closed() || is_unavailable {
+            DatabaseErrorKind::Unavailable
+        } else if code == &SqlState::UNIQUE_VIOLATION {
+            DatabaseErrorKind::UniqueViolation
+        } else if code == &SqlState::FOREIGN_KEY_VIOLATION {
+            DatabaseErrorKind::ForeignKeyViolation
+        } else {
+            DatabaseErrorKind::Query
+        };
+
+        let constraint = e
+            .as_db_error()
+            .and_then(|db_error| db_error.constraint())
+            .map(String::from);
+
+        return DatabaseError {
+            kind,
+            code: Some(String::from(code.code())),
+            constraint,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::{http::Status, local::blocking::Client, response::Responder};
+    use rocket_sync_db_pools::postgres::Client as PostgresClient;
+
+    use super::{DatabaseError, DatabaseErrorKind};
+    use crate::errors::{
+        domain::DomainRuleError, token::TokenError, url::UrlError, user::UserError,
+    };
+    use crate::test_db::TestDb;
+
+    fn status_of<E>(error: E) -> Status
+    where
+        E: for<'r> Responder<'r, 'static>,
+    {
+        let client = Client::untracked(rocket::build()).unwrap();
+        let request = client.get("/");
+
+        return match error.respond_to(&request) {
+            Ok(response) => response.status(),
+            Err(status) => status,
+        };
+    }
+
+    fn insert_user(client: &mut PostgresClient, client_id: &str) -> i32 {
+        let row = client
+            .query_one(
+                "INSERT INTO users (client_id, client_secret) VALUES ($1, 'secret') RETURNING id;",
+                &[&client_id],
+            )
+            .unwrap();
+
+        return row.get("id");
+    }
+
+    fn insert_token(
+        client: &mut PostgresClient,
+        user_id: i32,
+        name: &str,
+        token_hash: &str,
+    ) -> Result<u64, rocket_sync_db_pools::postgres::Error> {
+        return client.execute(
+            "INSERT INTO api_tokens (user_id, name, token_hash, prefix, scopes) VALUES ($1, $2, $3, 'prefix', '{}');",
+            &[&user_id, &name, &token_hash],
+        );
+    }
+
+    #[test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    fn unique_violations_of_known_constraints_are_conflicts() {
+        let test_db = TestDb::create();
+        let mut client = test_db.client();
+
+        let user_id = insert_user(&mut client, "owner");
+
+        let e = client
+            .execute(
+                "INSERT INTO users (client_id, client_secret) VALUES ('owner', 'secret');",
+                &[],
+            )
+            .unwrap_err();
+        let e = UserError::from(e);
+        assert!(matches!(e, UserError::ClientIdAlreadyExists));
+        assert_eq!(status_of(e), Status::Conflict);
+
+        let insert_url =
+            "INSERT INTO key_urls (key, url, user_id) VALUES ('taken', 'https://example.com', $1);";
+        client.execute(insert_url, &[&user_id]).unwrap();
+
+        let e = UrlError::from(client.execute(insert_url, &[&user_id]).unwrap_err());
+        assert!(matches!(e, UrlError::KeyAlreadyExists));
+        assert_eq!(status_of(e), Status::Conflict);
+
+        insert_token(&mut client, user_id, "ci", "hash-1").unwrap();
+
+        let e = TokenError::from(insert_token(&mut client, user_id, "ci", "hash-2").unwrap_err());
+        assert!(matches!(e, TokenError::NameAlreadyExists));
+        assert_eq!(status_of(e), Status::Conflict);
+
+        let insert_rule =
+            "INSERT INTO domain_rules (pattern, kind) VALUES ('example.com', 'deny');";
+        client.execute(insert_rule, &[]).unwrap();
+
+        let e = DomainRuleError::from(client.execute(insert_rule, &[]).unwrap_err());
+        assert!(matches!(e, DomainRuleError::RuleAlreadyExists));
+        assert_eq!(status_of(e), Status::Conflict);
+    }
+
+    #[test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    fn unique_violations_of_other_constraints_are_database_errors() {
+        let test_db = TestDb::create();
+        let mut client = test_db.client();
+
+        let user_id = insert_user(&mut client, "owner");
+        insert_token(&mut client, user_id, "ci", "hash").unwrap();
+
+        // Same hash under another name violates `api_tokens_token_hash_key`
+        let e = TokenError::from(insert_token(&mut client, user_id, "deploy", "hash").unwrap_err());
+
+        match &e {
+            TokenError::Database(e) => {
+                assert!(matches!(e.kind, DatabaseErrorKind::UniqueViolation));
+            }
+            e => panic!("expected a database error, got {:?}", e),
+        }
+
+        assert_eq!(status_of(e), Status::InternalServerError);
+    }
+
+    #[test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    fn lost_connections_are_unavailable() {
+        let test_db = TestDb::create();
+        let mut client = test_db.client();
+        let mut admin = test_db.client();
+
+        let row = client
+            .query_one("SELECT pg_backend_pid() AS pid;", &[])
+            .unwrap();
+        let pid: i32 = row.get("pid");
+
+        admin
+            .execute("SELECT pg_terminate_backend($1);", &[&pid])
+            .unwrap();
+
+        let e = client.query("SELECT 1;", &[]).unwrap_err();
+        let e = DatabaseError::from(e);
+        assert!(matches!(e.kind, DatabaseErrorKind::Unavailable));
+
+        let e = client.query("SELECT 1;", &[]).unwrap_err();
+        let e = UrlError::from(e);
+        assert!(matches!(e, UrlError::Database(_)));
+        assert_eq!(status_of(e), Status::ServiceUnavailable);
+    }
+}
@@ -0,0 +1,67 @@
+use rocket::{
+    http::Status,
+    response::{Responder, Result},
+    serde::json::Json,
+    Request,
+};
+
+use rocket_sync_db_pools::postgres::Error as PostgresError;
+use serde::Serialize;
+
+use super::database::DatabaseError;
+
+#[derive(Debug, Serialize)]
+pub enum DomainRuleError {
+    RuleAlreadyExists,
+    PatternInvalid,
+    NotFound,
+    Database(DatabaseError),
+    Unknown,
+}
+
+impl DomainRuleError {
+    fn bad_request<'r, 'o>(self, request: &'r Request<'_>) -> Result<'o> {
+        return self.with_status(request, Status::BadRequest);
+    }
+
+    fn with_status<'r, 'o>(self, request: &'r Request<'_>, status: Status) -> Result<'o> {
+        return Responder::respond_to(Json(self), request).map(|mut res| {
+            res.set_status(status);
+            return res;
+        });
+    }
+}
+
+/// Unique constraint on `domain_rules (pattern, kind)`
+const RULE_CONSTRAINT: &str = "domain_rules_pattern_kind_key";
+
+impl From<PostgresError> for DomainRuleError {
+    fn from(e: PostgresError) -> Self {
+        let e = DatabaseError::from(e);
+
+        if e.is_unique_violation_of(RULE_CONSTRAINT) {
+            return Self::RuleAlreadyExists;
+        }
+
+        return Self::Database(e);
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for DomainRuleError {
+    fn respond_to(self, request: &'r Request<'_>) -> Result<'o> {
+        return match self {
+            Self::PatternInvalid => self.bad_request(request),
+
+            Self::RuleAlreadyExists => self.with_status(request, Status::Conflict),
+
+            Self::NotFound => Err(Status::NotFound),
+
+            Self::Database(ref e) => {
+                let status = e.status();
+                self.with_status(request, status)
+            }
+
+            _ => Err(Status::InternalServerError),
+        };
+    }
+}
@@ -1,2 +1,6 @@
+pub mod audit;
+pub mod database;
+pub mod domain;
+pub mod token;
 pub mod url;
 pub mod user;
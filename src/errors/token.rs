@@ -0,0 +1,79 @@
+use rocket::{
+    http::Status,
+    response::{Responder, Result},
+    serde::json::Json,
+    Request,
+};
+
+use rocket_sync_db_pools::postgres::Error as PostgresError;
+use serde::Serialize;
+
+use super::database::DatabaseError;
+
+#[derive(Debug, Serialize)]
+pub enum TokenError {
+    NameAlreadyExists,
+    NameTooShort { min: usize },
+    NameTooLong { max: usize },
+    ScopesEmpty,
+    ScopeNotAllowed { scope: String },
+    ExpiryInvalid,
+    Invalid,
+    NotFound,
+    Database(DatabaseError),
+    Unknown,
+}
+
+impl TokenError {
+    fn bad_request<'r, 'o>(self, request: &'r Request<'_>) -> Result<'o> {
+        return self.with_status(request, Status::BadRequest);
+    }
+
+    fn with_status<'r, 'o>(self, request: &'r Request<'_>, status: Status) -> Result<'o> {
+        return Responder::respond_to(Json(self), request).map(|mut res| {
+            res.set_status(status);
+            return res;
+        });
+    }
+}
+
+/// Unique constraint on a user's token names
+const NAME_CONSTRAINT: &str = "api_tokens_user_id_name_key";
+
+impl From<PostgresError> for TokenError {
+    fn from(e: PostgresError) -> Self {
+        let e = DatabaseError::from(e);
+
+        if e.is_unique_violation_of(NAME_CONSTRAINT) {
+            return Self::NameAlreadyExists;
+        }
+
+        return Self::Database(e);
+    }
+}
+
+impl<'r, 'o: 'r> Responder<'r, 'o> for TokenError {
+    fn respond_to(self, request: &'r Request<'_>) -> Result<'o> {
+        return match self {
+            Self::NameTooShort { .. }
+            | Self::NameTooLong { .. }
+            | Self::ScopesEmpty
+            | Self::ExpiryInvalid => self.bad_request(request),
+
+            Self::NameAlreadyExists => self.with_status(request, Status::Conflict),
+
+            Self::ScopeNotAllowed { .. } => self.with_status(request, Status::Forbidden),
+
+            Self::Invalid => Err(Status::Unauthorized),
+
+            Self::NotFound => Err(Status::NotFound),
+
+            Self::Database(ref e) => {
+                let status = e.status();
+                self.with_status(request, status)
+            }
+
+            _ => Err(Status::InternalServerError),
+        };
+    }
+}
@@ -1,42 +1,124 @@
+use chrono::{DateTime, Utc};
 use rocket::{
     http::Status,
     response::{Responder, Result},
     serde::json::Json,
     Request,
 };
+use rocket_sync_db_pools::postgres::Error as PostgresError;
 use serde::Serialize;
 
+use super::database::DatabaseError;
+
 #[derive(Debug, Serialize)]
 pub enum UrlError {
     KeyAlreadyExists,
     KeyReserved { prefix: String },
+    KeySuffixReserved { suffix: String },
+    KeyNameReserved { key: String },
+    KeyRecentlyDeleted { available_at: DateTime<Utc> },
     KeyTooShort { min: usize },
     KeyTooLong { max: usize },
+    KeyGenerationFailed,
     UrlParseError(String),
     UrlInvalid,
+    TemplateInvalid { placeholder: String },
+    TemplateHostNotFixed,
+    TemplateRequiresPrefix,
+    WindowInvalid,
+    PassphraseTooShort { min: usize },
+    PassphraseTooLong { max: usize },
+    RedirectTypeInvalid { allowed: Vec<u16> },
+    QrSizeInvalid { min: u32, max: u32 },
+    QrMarginInvalid { max: u32 },
+    QrGenerationFailed,
+    DomainDenied { domain: String },
+    DomainNotAllowed { domain: String },
+    RedirectLoop { keys: Vec<String> },
+    RedirectChainTooLong { max: usize },
+    CursorInvalid,
+    ImportInvalid(String),
+    ImportTooLarge { max: usize },
+    OwnerDeleted,
+    UserNotFound,
+    CollaboratorIsOwner,
+    NotOwner,
     NotFound,
+    NotYetActive,
+    PreviewDisabled,
+    Expired,
+    HashError(String),
+    Database(DatabaseError),
     Unknown,
     UnexpectedUrlParseError,
 }
 
 impl UrlError {
     fn bad_request<'r, 'o>(self, request: &'r Request<'_>) -> Result<'o> {
+        return self.with_status(request, Status::BadRequest);
+    }
+
+    fn with_status<'r, 'o>(self, request: &'r Request<'_>, status: Status) -> Result<'o> {
         return Responder::respond_to(Json(self), request).map(|mut res| {
-            res.set_status(Status::BadRequest);
+            res.set_status(status);
             return res;
         });
     }
 }
 
+/// Primary key of `key_urls`
+const KEY_CONSTRAINT: &str = "key_urls_pkey";
+
+impl From<PostgresError> for UrlError {
+    fn from(e: PostgresError) -> Self {
+        let e = DatabaseError::from(e);
+
+        if e.is_unique_violation_of(KEY_CONSTRAINT) {
+            return Self::KeyAlreadyExists;
+        }
+
+        return Self::Database(e);
+    }
+}
+
 impl<'r, 'o: 'r> Responder<'r, 'o> for UrlError {
     fn respond_to(self, request: &'r Request<'_>) -> Result<'o> {
         return match self {
-            Self::KeyAlreadyExists
+            Self::KeyReserved { .. }
+            | Self::KeySuffixReserved { .. }
+            | Self::KeyNameReserved { .. }
+            | Self::KeyRecentlyDeleted { .. }
             | Self::KeyTooShort { .. }
             | Self::KeyTooLong { .. }
             | Self::UrlParseError(_)
-            | Self::UrlInvalid => self.bad_request(request),
-            Self::NotFound => Err(Status::NotFound),
+            | Self::UrlInvalid
+            | Self::TemplateInvalid { .. }
+            | Self::TemplateHostNotFixed
+            | Self::TemplateRequiresPrefix
+            | Self::WindowInvalid
+            | Self::PassphraseTooShort { .. }
+            | Self::PassphraseTooLong { .. }
+            | Self::RedirectTypeInvalid { .. }
+            | Self::QrSizeInvalid { .. }
+            | Self::QrMarginInvalid { .. }
+            | Self::DomainDenied { .. }
+            | Self::DomainNotAllowed { .. }
+            | Self::RedirectLoop { .. }
+            | Self::RedirectChainTooLong { .. }
+            | Self::CursorInvalid
+            | Self::OwnerDeleted
+            | Self::UserNotFound
+            | Self::CollaboratorIsOwner
+            | Self::ImportInvalid(_) => self.bad_request(request),
+            Self::KeyAlreadyExists => self.with_status(request, Status::Conflict),
+            Self::ImportTooLarge { .. } => self.with_status(request, Status::PayloadTooLarge),
+            Self::PreviewDisabled | Self::NotOwner => self.with_status(request, Status::Forbidden),
+            Self::NotFound | Self::NotYetActive => Err(Status::NotFound),
+            Self::Expired => Err(Status::Gone),
+            Self::Database(ref e) => {
+                let status = e.status();
+                self.with_status(request, status)
+            }
             _ => Err(Status::InternalServerError),
         };
     }
@@ -5,9 +5,10 @@ use rocket::{
     Request,
 };
 
+use rocket_sync_db_pools::postgres::Error as PostgresError;
 use serde::Serialize;
 
-use super::url::UrlError;
+use super::database::DatabaseError;
 
 #[derive(Debug, Serialize)]
 pub enum UserError {
@@ -16,35 +17,65 @@ pub enum UserError {
     ClientIdTooLong { max: usize },
     ClientSecretTooShort { min: usize },
     ClientSecretTooLong { max: usize },
-    UrlDeletionError(UrlError),
+    CursorInvalid,
     Invalid,
+    LockedOut { retry_after: u64 },
     NotFound,
     HashError(String),
+    Database(DatabaseError),
     Unknown,
 }
 
 impl UserError {
     fn bad_request<'r, 'o>(self, request: &'r Request<'_>) -> Result<'o> {
+        return self.with_status(request, Status::BadRequest);
+    }
+
+    fn with_status<'r, 'o>(self, request: &'r Request<'_>, status: Status) -> Result<'o> {
         return Responder::respond_to(Json(self), request).map(|mut res| {
-            res.set_status(Status::BadRequest);
+            res.set_status(status);
             return res;
         });
     }
 }
 
+/// Unique constraint on `users.client_id`
+const CLIENT_ID_CONSTRAINT: &str = "users_client_id_key";
+
+impl From<PostgresError> for UserError {
+    fn from(e: PostgresError) -> Self {
+        let e = DatabaseError::from(e);
+
+        if e.is_unique_violation_of(CLIENT_ID_CONSTRAINT) {
+            return Self::ClientIdAlreadyExists;
+        }
+
+        return Self::Database(e);
+    }
+}
+
 impl<'r, 'o: 'r> Responder<'r, 'o> for UserError {
     fn respond_to(self, request: &'r Request<'_>) -> Result<'o> {
         return match self {
-            Self::ClientIdAlreadyExists
-            | Self::ClientIdTooShort { .. }
+            Self::ClientIdTooShort { .. }
             | Self::ClientIdTooLong { .. }
             | Self::ClientSecretTooShort { .. }
-            | Self::ClientSecretTooLong { .. } => self.bad_request(request),
+            | Self::ClientSecretTooLong { .. }
+            | Self::CursorInvalid => self.bad_request(request),
+
+            Self::ClientIdAlreadyExists => self.with_status(request, Status::Conflict),
 
             Self::Invalid => Err(Status::Unauthorized),
 
+            Self::LockedOut { .. } => self.with_status(request, Status::TooManyRequests),
+
             Self::NotFound => Err(Status::NotFound),
 
+            Self::Database(ref e) => {
+                let status = e.status();
+                self.with_status(request, status)
+            }
+
             _ => Err(Status::InternalServerError),
         };
     }
@@ -1,25 +1,62 @@
 #[macro_use]
 extern crate rocket;
 
-#[macro_use]
-extern crate lazy_static;
-
 extern crate argon2;
 
+mod clicks;
 mod config;
 mod controllers;
 mod errors;
+mod migrations;
+mod purge;
 mod services;
 mod utils;
 
-use config::{database::DbConnection, environment};
+#[cfg(test)]
+mod test_db;
+
+use config::{
+    app, cache, cors, database::DbConnection, deletion, environment, lockout, rate_limit, redirect,
+    unlock,
+};
 
 #[rocket::main]
 async fn main() -> Result<(), rocket::Error> {
     environment::init_env();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().map(String::as_str) == Some("migrate") {
+        return migrations::command(args.get(1).map(String::as_str)).await;
+    }
+
+    let app_config = app::build_app_config();
+
+    if args.first().map(String::as_str) == Some("purge") {
+        return purge::command(deletion::build_deletion_retention(&app_config)).await;
+    }
+
     let rocket = rocket::build();
     let rocket = rocket.attach(DbConnection::fairing());
+
+    let rocket = if app_config.run_migrations_on_startup {
+        rocket.attach(migrations::fairing())
+    } else {
+        rocket
+    };
+    let rocket = rocket.manage(cache::build_redirect_cache_ref(&app_config));
+    let rocket = rocket.manage(redirect::build_default_redirect_type(&app_config));
+    let rocket = rocket.manage(unlock::build_unlock_limiter(&app_config));
+    let rocket = rocket.manage(cors::build_cors_policy(&app_config));
+    let rocket = rocket.manage(rate_limit::build_rate_limits(&app_config));
+    let rocket = rocket.manage(lockout::build_credential_lockouts(&app_config));
+    let rocket = rocket.manage(deletion::build_deletion_retention(&app_config));
+    let rocket = rocket.manage(deletion::build_purge_interval(&app_config));
+    let rocket = rocket.manage(app::build_key_generator_ref(&app_config));
+    let rocket = rocket.manage(app::build_argon2_config_ref(&app_config));
+    let rocket = rocket.manage(app_config);
+    let rocket = rocket.attach(clicks::fairing());
+    let rocket = rocket.attach(purge::fairing());
     let rocket = controllers::mount(rocket);
 
     return rocket.launch().await;
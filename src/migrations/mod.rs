@@ -0,0 +1,234 @@
+use rocket::{fairing::AdHoc, Build, Rocket};
+use rocket_sync_db_pools::postgres::{Client, Error as PostgresError};
+
+use crate::config::database::DbConnection;
+
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    up: &'static str,
+    down: &'static str,
+}
+
+macro_rules! migration {
+    ($version:expr, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            up: include_str!(concat!("../../resources/migrations/", $name, ".up.sql")),
+            down: include_str!(concat!("../../resources/migrations/", $name, ".down.sql")),
+        }
+    };
+}
+
+/// Every migration known to this build, in the order they must be applied.
+pub static MIGRATIONS: &[Migration] = &[
+    migration!(1, "0001_create_users_and_key_urls"),
+    migration!(2, "0002_create_url_clicks"),
+    migration!(3, "0003_add_key_urls_window"),
+    migration!(4, "0004_create_api_tokens"),
+    migration!(5, "0005_add_key_urls_redirect_type"),
+    migration!(6, "0006_add_key_urls_is_prefix"),
+    migration!(7, "0007_add_key_urls_passphrase"),
+    migration!(8, "0008_add_key_urls_preview"),
+    migration!(9, "0009_create_domain_rules"),
+    migration!(10, "0010_create_audit_log"),
+    migration!(11, "0011_add_soft_delete"),
+    migration!(12, "0012_create_key_url_collaborators"),
+];
+
+pub struct MigrationStatus {
+    pub migration: &'static Migration,
+    pub applied: bool,
+}
+
+fn ensure_migrations_table(client: &mut Client) -> Result<(), PostgresError> {
+    client.batch_execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INT UNIQUE PRIMARY KEY NOT NULL,
+            name VARCHAR(256) NOT NULL,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );",
+    )?;
+
+    return Ok(());
+}
+
+fn applied_versions(client: &mut Client) -> Result<Vec<i32>, PostgresError> {
+    ensure_migrations_table(client)?;
+
+    let mut versions = vec![];
+
+    for row in client.query(
+        "SELECT version FROM schema_migrations ORDER BY version ASC;",
+        &[],
+    )? {
+        let version: i32 = row.get("version");
+        versions.push(version);
+    }
+
+    return Ok(versions);
+}
+
+/// Applies every pending migration, each in its own transaction.
+/// Returns the migrations that were applied.
+pub fn up(client: &mut Client) -> Result<Vec<&'static Migration>, PostgresError> {
+    let applied = applied_versions(client)?;
+
+    let mut migrated = vec![];
+
+    for migration in MIGRATIONS {
+        if applied.contains(&migration.version) {
+            continue;
+        }
+
+        let mut transaction = client.transaction()?;
+
+        transaction.batch_execute(migration.up)?;
+        transaction.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES ($1, $2);",
+            &[&migration.version, &migration.name],
+        )?;
+
+        transaction.commit()?;
+
+        migrated.push(migration);
+    }
+
+    return Ok(migrated);
+}
+
+/// Reverts the most recently applied migration, if any.
+pub fn down(client: &mut Client) -> Result<Option<&'static Migration>, PostgresError> {
+    let applied = applied_versions(client)?;
+
+    let migration = match MIGRATIONS
+        .iter()
+        .rev()
+        .find(|migration| applied.contains(&migration.version))
+    {
+        Some(migration) => migration,
+        None => return Ok(None),
+    };
+
+    let mut transaction = client.transaction()?;
+
+    transaction.batch_execute(migration.down)?;
+    transaction.execute(
+        "DELETE FROM schema_migrations WHERE version = $1;",
+        &[&migration.version],
+    )?;
+
+    transaction.commit()?;
+
+    return Ok(Some(migration));
+}
+
+pub fn status(client: &mut Client) -> Result<Vec<MigrationStatus>, PostgresError> {
+    let applied = applied_versions(client)?;
+
+    return Ok(MIGRATIONS
+        .iter()
+        .map(|migration| MigrationStatus {
+            migration,
+            applied: applied.contains(&migration.version),
+        })
+        .collect());
+}
+
+/// Applies pending migrations once the database pool has been initialized.
+/// Must be attached after `DbConnection::fairing()`.
+pub fn fairing() -> AdHoc {
+    return AdHoc::try_on_ignite("Database migrations", |rocket| async {
+        return match run_up(&rocket).await {
+            Ok(()) => Ok(rocket),
+            Err(()) => Err(rocket),
+        };
+    });
+}
+
+async fn run_up(rocket: &Rocket<Build>) -> Result<(), ()> {
+    let db = match DbConnection::get_one(rocket).await {
+        Some(db) => db,
+        None => {
+            error!("Unable to get a database connection to run migrations");
+            return Err(());
+        }
+    };
+
+    return match db.run(up).await {
+        Ok(migrated) => {
+            for migration in migrated {
+                info!("Applied migration {}", migration.name);
+            }
+
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to apply migrations: {}", e);
+            Err(())
+        }
+    };
+}
+
+/// Runs the `migrate up|down|status` command against the configured database.
+pub async fn command(action: Option<&str>) -> Result<(), rocket::Error> {
+    let action = match action {
+        Some(action @ ("up" | "down" | "status")) => String::from(action),
+        _ => {
+            eprintln!("Usage: url-linker migrate <up|down|status>");
+            std::process::exit(2);
+        }
+    };
+
+    let rocket = rocket::build()
+        .attach(DbConnection::fairing())
+        .ignite()
+        .await?;
+
+    let db = match DbConnection::get_one(&rocket).await {
+        Some(db) => db,
+        None => {
+            eprintln!("Unable to get a database connection");
+            std::process::exit(1);
+        }
+    };
+
+    let result = db
+        .run(move |client| -> Result<(), PostgresError> {
+            match action.as_str() {
+                "up" => {
+                    let migrated = up(client)?;
+
+                    if migrated.is_empty() {
+                        println!("No pending migrations");
+                    }
+
+                    for migration in migrated {
+                        println!("Applied {}", migration.name);
+                    }
+                }
+                "down" => match down(client)? {
+                    Some(migration) => println!("Reverted {}", migration.name),
+                    None => println!("No applied migrations to revert"),
+                },
+                _ => {
+                    for status in status(client)? {
+                        let state = if status.applied { "applied" } else { "pending" };
+
+                        println!("{} {}", state, status.migration.name);
+                    }
+                }
+            }
+
+            return Ok(());
+        })
+        .await;
+
+    if let Err(e) = result {
+        eprintln!("Migration failed: {}", e);
+        std::process::exit(1);
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use rocket::{fairing::AdHoc, tokio};
+use rocket_sync_db_pools::postgres::{Client, Error as PostgresError, GenericClient, NoTls};
+
+use crate::config::database::DbConnection;
+use crate::config::deletion::{DeletionRetention, PurgeInterval};
+
+pub struct PurgeReport {
+    pub urls: u64,
+    pub users: u64,
+}
+
+/// Permanently removes URLs and users deleted before `cutoff`. A user's URLs
+/// are deleted no later than the user, so they are gone by the time the user
+/// is purged. Users that still own URLs are kept.
+pub fn purge(
+    client: &mut impl GenericClient,
+    cutoff: DateTime<Utc>,
+) -> Result<PurgeReport, PostgresError> {
+    let urls = client.execute("DELETE FROM key_urls WHERE deleted_at < $1;", &[&cutoff])?;
+
+    let users = client.execute(
+        "DELETE FROM users WHERE deleted_at < $1 AND NOT EXISTS (SELECT 1 FROM key_urls WHERE key_urls.user_id = users.id);",
+        &[&cutoff],
+    )?;
+
+    return Ok(PurgeReport { urls, users });
+}
+
+/// Periodically purges deleted URLs and users past retention once the server
+/// has started. Each run opens its own connection rather than holding one of
+/// the pool's.
+pub fn fairing() -> AdHoc {
+    return AdHoc::on_liftoff("Purge deleted", |rocket| {
+        Box::pin(async move {
+            let interval = match rocket.state::<PurgeInterval>() {
+                Some(PurgeInterval(Some(interval))) => *interval,
+                _ => return,
+            };
+
+            let retention = match rocket.state::<DeletionRetention>() {
+                Some(retention) => retention.0,
+                None => return,
+            };
+
+            let url = match rocket
+                .figment()
+                .extract_inner::<String>("databases.url_linker.url")
+            {
+                Ok(url) => url,
+                Err(e) => {
+                    error!("Unable to read the database configuration to purge: {}", e);
+                    return;
+                }
+            };
+
+            tokio::spawn(async move {
+                let mut ticks = tokio::time::interval(interval);
+
+                loop {
+                    ticks.tick().await;
+
+                    let url = url.clone();
+
+                    let result = tokio::task::spawn_blocking(move || {
+                        let mut client = Client::connect(&url, NoTls)?;
+
+                        return purge(&mut client, Utc::now() - retention);
+                    })
+                    .await;
+
+                    match result {
+                        Ok(Ok(report)) => log_report(&report),
+                        Ok(Err(e)) => error!("Failed to purge deleted items: {}", e),
+                        Err(e) => error!("Purge job panicked: {}", e),
+                    }
+                }
+            });
+        })
+    });
+}
+
+fn log_report(report: &PurgeReport) {
+    if report.urls > 0 || report.users > 0 {
+        info!(
+            "Purged {} deleted URLs and {} deleted users",
+            report.urls, report.users
+        );
+    }
+}
+
+/// Runs the `purge` command against the configured database.
+pub async fn command(retention: DeletionRetention) -> Result<(), rocket::Error> {
+    let rocket = rocket::build()
+        .attach(DbConnection::fairing())
+        .ignite()
+        .await?;
+
+    let db = match DbConnection::get_one(&rocket).await {
+        Some(db) => db,
+        None => {
+            eprintln!("Unable to get a database connection");
+            std::process::exit(1);
+        }
+    };
+
+    let result = db
+        .run(move |client| purge(client, Utc::now() - retention.0))
+        .await;
+
+    match result {
+        Ok(report) => println!(
+            "Purged {} deleted URLs and {} deleted users",
+            report.urls, report.users
+        ),
+        Err(e) => {
+            eprintln!("Purge failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    return Ok(());
+}
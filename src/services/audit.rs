@@ -0,0 +1,212 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::json::{json, serde_json, Value};
+use rocket_sync_db_pools::postgres::{types::ToSql, Error as PostgresError, GenericClient, Row};
+
+use crate::config::database::DbConnection;
+use crate::errors::audit::AuditError;
+
+use super::types::{
+    audit::{AuditEntry, AuditQuery, NewAuditEntry},
+    lockout::LockoutStatus,
+    page::Page,
+    url::Url,
+    user::User,
+};
+
+/// Stands in for secrets in snapshots, showing that one is set or changed
+/// without revealing it.
+const REDACTED: &str = "[redacted]";
+
+#[rocket::async_trait]
+pub trait AuditService: Send + Sync {
+    /// Entries are returned newest first.
+    async fn get_all(&self, query: AuditQuery) -> Result<Page<AuditEntry>, AuditError>;
+}
+
+pub struct DbAuditService {
+    db: DbConnection,
+}
+
+impl DbAuditService {
+    pub fn new(db: DbConnection) -> Self {
+        return Self { db };
+    }
+}
+
+/// Appends an entry to the audit log. Call it within the transaction making
+/// the change, so that the entry is only kept if the change is.
+pub fn record(client: &mut impl GenericClient, entry: NewAuditEntry) -> Result<(), PostgresError> {
+    let action = entry.action.as_str();
+    let target_type = entry.target.kind();
+    let target_id = entry.target.id();
+    let before = entry.before.map(|value| value.to_string());
+    let after = entry.after.map(|value| value.to_string());
+
+    client.execute(
+        "INSERT INTO audit_log (actor_id, action, target_type, target_id, before, after) VALUES ($1, $2, $3, $4, $5::TEXT::JSONB, $6::TEXT::JSONB);",
+        &[&entry.actor_id, &action, &target_type, &target_id, &before, &after],
+    )?;
+
+    return Ok(());
+}
+
+pub fn url_snapshot(url: &Url) -> Value {
+    return json!({
+        "key": url.key,
+        "url": url.url,
+        "userId": url.user_id,
+        "activatesAt": url.activates_at,
+        "expiresAt": url.expires_at,
+        "redirectType": url.redirect_type.map(|redirect_type| redirect_type.code()),
+        "isPrefix": url.is_prefix,
+        "passphrase": url.passphrase_hash.as_ref().map(|_| REDACTED),
+        "previewEnabled": url.preview_enabled,
+    });
+}
+
+pub fn collaborator_snapshot(user_id: i32) -> Value {
+    return json!({ "collaboratorId": user_id });
+}
+
+/// Client secrets are only hashed at rest, and never part of a snapshot unless
+/// `secret_changed`, which marks the change.
+pub fn user_snapshot(user: &User, secret_changed: bool) -> Value {
+    let mut snapshot = json!({
+        "id": user.id,
+        "clientId": user.client_id,
+        "isAdmin": user.is_admin,
+    });
+
+    if secret_changed {
+        snapshot["clientSecret"] = json!(REDACTED);
+    }
+
+    return snapshot;
+}
+
+pub fn lockout_snapshot(status: &LockoutStatus) -> Value {
+    return json!({
+        "failures": status.failures,
+        "lockouts": status.lockouts,
+        "lockedUntil": status.locked_until,
+    });
+}
+
+fn row_to_audit_entry(row: &Row) -> AuditEntry {
+    let id: i64 = row.get("id");
+    let actor_id: Option<i32> = row.get("actor_id");
+
+    let value: &str = row.get("action");
+    let action = String::from(value);
+
+    let value: &str = row.get("target_type");
+    let target_type = String::from(value);
+
+    let value: &str = row.get("target_id");
+    let target_id = String::from(value);
+
+    let value: Option<&str> = row.get("before");
+    let before = value.and_then(|value| serde_json::from_str(value).ok());
+
+    let value: Option<&str> = row.get("after");
+    let after = value.and_then(|value| serde_json::from_str(value).ok());
+
+    let created_at: DateTime<Utc> = row.get("created_at");
+
+    return AuditEntry {
+        id,
+        actor_id,
+        action,
+        target_type,
+        target_id,
+        before,
+        after,
+        created_at,
+    };
+}
+
+#[rocket::async_trait]
+impl AuditService for DbAuditService {
+    async fn get_all(&self, query: AuditQuery) -> Result<Page<AuditEntry>, AuditError> {
+        let limit = query.page.limit();
+        let offset = query.page.offset().ok_or(AuditError::CursorInvalid)?;
+
+        if let (Some(from), Some(to)) = (&query.from, &query.to) {
+            if from > to {
+                return Err(AuditError::RangeInvalid);
+            }
+        }
+
+        let mut conditions: Vec<String> = vec![];
+        let mut params: Vec<Box<dyn ToSql + Sync + Send>> = vec![];
+
+        if let Some(actor_id) = query.actor_id {
+            params.push(Box::new(actor_id));
+            conditions.push(format!("actor_id = ${}", params.len()));
+        }
+
+        if let Some(action) = query.action {
+            params.push(Box::new(action));
+            conditions.push(format!("action = ${}", params.len()));
+        }
+
+        if let Some(target_type) = query.target_type {
+            params.push(Box::new(target_type));
+            conditions.push(format!("target_type = ${}", params.len()));
+        }
+
+        if let Some(target_id) = query.target_id {
+            params.push(Box::new(target_id.to_ascii_lowercase()));
+            conditions.push(format!("target_id = ${}", params.len()));
+        }
+
+        if let Some(from) = query.from {
+            params.push(Box::new(from));
+            conditions.push(format!("created_at >= ${}", params.len()));
+        }
+
+        if let Some(to) = query.to {
+            params.push(Box::new(to));
+            conditions.push(format!("created_at < ${}", params.len()));
+        }
+
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+
+        return self
+            .db
+            .run(move |connection| {
+                let params: Vec<&(dyn ToSql + Sync)> = params
+                    .iter()
+                    .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+                    .collect();
+
+                let mut total = 0;
+
+                for row in connection.query(
+                    format!("SELECT COUNT(*) AS total FROM audit_log{filter};").as_str(),
+                    &params,
+                )? {
+                    total = row.get("total");
+                }
+
+                let entries = connection
+                    .query(
+                        format!(
+                            "SELECT id, actor_id, action, target_type, target_id, before::TEXT AS before, after::TEXT AS after, created_at FROM audit_log{filter} ORDER BY created_at DESC, id DESC LIMIT {limit} OFFSET {offset};"
+                        )
+                        .as_str(),
+                        &params,
+                    )?
+                    .iter()
+                    .map(row_to_audit_entry)
+                    .collect();
+
+                return Ok(Page::new(entries, total, offset));
+            })
+            .await;
+    }
+}
@@ -0,0 +1,187 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+
+use super::types::{cache::RedirectCacheStats, url::Url};
+
+pub type RedirectCacheRef = std::sync::Arc<RedirectCache>;
+
+/// Taken before reading a URL from the database and handed back to
+/// `RedirectCache::insert`, which drops the URL if any invalidation happened
+/// in between, since the read may predate the write that caused it.
+pub struct CacheGeneration(u64);
+
+struct CacheEntry {
+    url: Url,
+    cached_at: Instant,
+}
+
+/// Bounded LRU cache of URLs by key, used to serve redirects without a
+/// database round trip. Entries also expire after a fixed time to live.
+pub struct RedirectCache {
+    entries: Mutex<LruCache<String, CacheEntry>>,
+    capacity: usize,
+    ttl: Duration,
+    /// Bumped by every invalidation, while holding the entries lock
+    generation: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl RedirectCache {
+    pub fn new(capacity: NonZeroUsize, ttl: Duration) -> RedirectCache {
+        return RedirectCache {
+            entries: Mutex::new(LruCache::new(capacity)),
+            capacity: capacity.get(),
+            ttl,
+            generation: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        };
+    }
+
+    pub fn get(&self, key: &str) -> Option<Url> {
+        let mut entries = self.entries.lock().unwrap();
+
+        let url = match entries.get(key) {
+            Some(entry) if entry.cached_at.elapsed() < self.ttl => Some(entry.url.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        };
+
+        if url.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        return url;
+    }
+
+    pub fn generation(&self) -> CacheGeneration {
+        return CacheGeneration(self.generation.load(Ordering::SeqCst));
+    }
+
+    pub fn insert(&self, url: Url, generation: CacheGeneration) {
+        let mut entries = self.entries.lock().unwrap();
+
+        if self.generation.load(Ordering::SeqCst) != generation.0 {
+            return;
+        }
+
+        let entry = CacheEntry {
+            url: url.clone(),
+            cached_at: Instant::now(),
+        };
+
+        entries.put(url.key, entry);
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        let mut entries = self.entries.lock().unwrap();
+
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        entries.pop(key);
+    }
+
+    pub fn invalidate_by_user_id(&self, user_id: i32) {
+        let mut entries = self.entries.lock().unwrap();
+
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        let keys: Vec<String> = entries
+            .iter()
+            .filter(|(_, entry)| entry.url.user_id == user_id)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in keys {
+            entries.pop(&key);
+        }
+    }
+
+    pub fn stats(&self) -> RedirectCacheStats {
+        return RedirectCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            size: self.entries.lock().unwrap().len(),
+            capacity: self.capacity,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+
+    use chrono::Utc;
+
+    use super::RedirectCache;
+    use crate::services::types::url::Url;
+
+    fn url(key: &str, destination: &str) -> Url {
+        return Url {
+            key: String::from(key),
+            url: String::from(destination),
+            user_id: 1,
+            activates_at: None,
+            expires_at: None,
+            redirect_type: None,
+            is_prefix: false,
+            passphrase_hash: None,
+            preview_enabled: true,
+            created_at: Utc::now(),
+            deleted_at: None,
+        };
+    }
+
+    fn cache() -> RedirectCache {
+        return RedirectCache::new(NonZeroUsize::new(10).unwrap(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn caches_reads_when_nothing_was_invalidated() {
+        let cache = cache();
+
+        let generation = cache.generation();
+        cache.insert(url("a", "https://example.com"), generation);
+
+        assert_eq!(cache.get("a").unwrap().url, "https://example.com");
+    }
+
+    #[test]
+    fn drops_reads_that_an_invalidation_overtook() {
+        let cache = cache();
+
+        // A miss reads the old destination, then an update invalidates the key
+        // before the read is cached
+        let generation = cache.generation();
+        cache.invalidate("a");
+        cache.insert(url("a", "https://old.example.com"), generation);
+
+        assert!(cache.get("a").is_none());
+
+        let generation = cache.generation();
+        cache.insert(url("a", "https://new.example.com"), generation);
+
+        assert_eq!(cache.get("a").unwrap().url, "https://new.example.com");
+    }
+
+    #[test]
+    fn invalidating_by_user_also_drops_overtaken_reads() {
+        let cache = cache();
+
+        let generation = cache.generation();
+        cache.invalidate_by_user_id(1);
+        cache.insert(url("a", "https://example.com"), generation);
+
+        assert!(cache.get("a").is_none());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::config::database::DbConnection;
+use crate::errors::url::UrlError;
+
+use super::types::click::{ClickStats, DailyClicks};
+
+#[rocket::async_trait]
+pub trait ClickService: Send + Sync {
+    async fn get_stats_by_key(&self, key: String) -> Result<ClickStats, UrlError>;
+
+    async fn count_by_key(&self, key: String) -> Result<i64, UrlError>;
+}
+
+pub struct DbClickService {
+    db: DbConnection,
+}
+
+impl DbClickService {
+    pub fn new(db: DbConnection) -> Self {
+        return Self { db };
+    }
+}
+
+#[rocket::async_trait]
+impl ClickService for DbClickService {
+    async fn get_stats_by_key(&self, key: String) -> Result<ClickStats, UrlError> {
+        let key = key.to_ascii_lowercase();
+
+        return self
+            .db
+            .run(move |connection| {
+                let mut total_clicks = 0;
+                let mut unique_visitors = 0;
+
+                for row in connection
+                    .query(
+                        "SELECT COUNT(*) AS clicks, COUNT(DISTINCT ip_hash) AS unique_visitors FROM url_clicks WHERE key = $1;",
+                        &[&key],
+                    )?
+                {
+                    total_clicks = row.get("clicks");
+                    unique_visitors = row.get("unique_visitors");
+                }
+
+                let mut days = vec![];
+
+                for row in connection
+                    .query(
+                        "SELECT to_char(clicked_at AT TIME ZONE 'UTC', 'YYYY-MM-DD') AS date, COUNT(*) AS clicks, COUNT(DISTINCT ip_hash) AS unique_visitors FROM url_clicks WHERE key = $1 GROUP BY date ORDER BY date ASC;",
+                        &[&key],
+                    )?
+                {
+                    let value: &str = row.get("date");
+                    let date = String::from(value);
+
+                    let clicks: i64 = row.get("clicks");
+                    let unique_visitors: i64 = row.get("unique_visitors");
+
+                    days.push(DailyClicks {
+                        date,
+                        clicks,
+                        unique_visitors,
+                    });
+                }
+
+                return Ok(ClickStats {
+                    key,
+                    total_clicks,
+                    unique_visitors,
+                    days,
+                });
+            })
+            .await;
+    }
+
+    async fn count_by_key(&self, key: String) -> Result<i64, UrlError> {
+        let key = key.to_ascii_lowercase();
+
+        return self
+            .db
+            .run(move |connection| -> Result<i64, UrlError> {
+                for row in connection.query(
+                    "SELECT COUNT(*) AS clicks FROM url_clicks WHERE key = $1;",
+                    &[&key],
+                )? {
+                    return Ok(row.get("clicks"));
+                }
+
+                return Ok(0);
+            })
+            .await;
+    }
+}
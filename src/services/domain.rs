@@ -0,0 +1,272 @@
+use chrono::{DateTime, Utc};
+use rocket_sync_db_pools::postgres::{Error as PostgresError, GenericClient, Row};
+
+use crate::config::database::DbConnection;
+use crate::errors::{domain::DomainRuleError, url::UrlError};
+
+use super::types::domain::{CreateDomainRuleRequest, DomainRule, DomainRuleKind};
+
+#[rocket::async_trait]
+pub trait DomainRuleService: Send + Sync {
+    async fn create(&self, rule: CreateDomainRuleRequest) -> Result<DomainRule, DomainRuleError>;
+
+    async fn get_all(
+        &self,
+        kind: Option<DomainRuleKind>,
+    ) -> Result<Vec<DomainRule>, DomainRuleError>;
+
+    async fn delete_by_id(&self, id: i32) -> Result<(), DomainRuleError>;
+}
+
+pub struct DbDomainRuleService {
+    db: DbConnection,
+}
+
+impl DbDomainRuleService {
+    pub fn new(db: DbConnection) -> Self {
+        return Self { db };
+    }
+}
+
+/// Normalizes a pattern to a lowercase domain without a trailing dot.
+fn normalize_pattern(pattern: &str) -> Result<String, DomainRuleError> {
+    const MAX: usize = 253;
+
+    let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+
+    let valid = !pattern.is_empty()
+        && pattern.len() <= MAX
+        && pattern.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    if !valid {
+        return Err(DomainRuleError::PatternInvalid);
+    }
+
+    return Ok(pattern);
+}
+
+fn row_to_domain_rule(row: &Row) -> DomainRule {
+    let id: i32 = row.get("id");
+
+    let value: &str = row.get("pattern");
+    let pattern = String::from(value);
+
+    let value: &str = row.get("kind");
+    let kind = DomainRuleKind::from_str(value).unwrap_or(DomainRuleKind::Deny);
+
+    let created_at: DateTime<Utc> = row.get("created_at");
+
+    return DomainRule {
+        id,
+        pattern,
+        kind,
+        created_at,
+    };
+}
+
+pub fn load_domain_rules(
+    client: &mut impl GenericClient,
+) -> Result<Vec<DomainRule>, PostgresError> {
+    return Ok(client
+        .query(
+            "SELECT id, pattern, kind, created_at FROM domain_rules;",
+            &[],
+        )?
+        .iter()
+        .map(row_to_domain_rule)
+        .collect());
+}
+
+/// Denied domains are always rejected. Once any domain is allowed, every
+/// other domain is rejected too.
+pub fn check_domain(rules: &[DomainRule], host: &str) -> Result<(), UrlError> {
+    let domain = host.trim_end_matches('.').to_ascii_lowercase();
+
+    if rules
+        .iter()
+        .any(|rule| rule.kind == DomainRuleKind::Deny && rule.matches(&domain))
+    {
+        return Err(UrlError::DomainDenied { domain });
+    }
+
+    let mut allow_rules = rules
+        .iter()
+        .filter(|rule| rule.kind == DomainRuleKind::Allow)
+        .peekable();
+
+    if allow_rules.peek().is_some() && !allow_rules.any(|rule| rule.matches(&domain)) {
+        return Err(UrlError::DomainNotAllowed { domain });
+    }
+
+    return Ok(());
+}
+
+#[rocket::async_trait]
+impl DomainRuleService for DbDomainRuleService {
+    async fn create(&self, rule: CreateDomainRuleRequest) -> Result<DomainRule, DomainRuleError> {
+        let pattern = normalize_pattern(&rule.pattern)?;
+        let kind = rule.kind.as_str();
+
+        return self
+            .db
+            .run(move |connection| {
+                for row in connection.query(
+                    "INSERT INTO domain_rules (pattern, kind) VALUES ($1, $2) RETURNING id, pattern, kind, created_at;",
+                    &[&pattern, &kind],
+                )? {
+                    return Ok(row_to_domain_rule(&row));
+                }
+
+                return Err(DomainRuleError::Unknown);
+            })
+            .await;
+    }
+
+    async fn get_all(
+        &self,
+        kind: Option<DomainRuleKind>,
+    ) -> Result<Vec<DomainRule>, DomainRuleError> {
+        let kind = kind.map(|kind| kind.as_str());
+
+        return self
+            .db
+            .run(move |connection| -> Result<Vec<DomainRule>, DomainRuleError> {
+                let rules = connection
+                    .query(
+                        "SELECT id, pattern, kind, created_at FROM domain_rules WHERE $1::VARCHAR IS NULL OR kind = $1 ORDER BY pattern ASC, kind ASC;",
+                        &[&kind],
+                    )?
+                    .iter()
+                    .map(row_to_domain_rule)
+                    .collect();
+
+                return Ok(rules);
+            })
+            .await;
+    }
+
+    async fn delete_by_id(&self, id: i32) -> Result<(), DomainRuleError> {
+        return self
+            .db
+            .run(move |connection| {
+                let rows = connection.execute("DELETE FROM domain_rules WHERE id = $1;", &[&id])?;
+
+                if rows != 1 {
+                    return Err(DomainRuleError::NotFound);
+                }
+
+                return Ok(());
+            })
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::{check_domain, normalize_pattern};
+    use crate::errors::url::UrlError;
+    use crate::services::types::domain::{DomainRule, DomainRuleKind};
+
+    fn rule(pattern: &str, kind: DomainRuleKind) -> DomainRule {
+        return DomainRule {
+            id: 1,
+            pattern: String::from(pattern),
+            kind,
+            created_at: Utc::now(),
+        };
+    }
+
+    #[test]
+    fn normalizes_patterns() {
+        assert_eq!(normalize_pattern(" Example.COM. ").unwrap(), "example.com");
+        assert_eq!(
+            normalize_pattern("xn--bcher-kva.de").unwrap(),
+            "xn--bcher-kva.de"
+        );
+    }
+
+    #[test]
+    fn rejects_patterns_that_arent_domains() {
+        for pattern in [
+            "",
+            ".",
+            "*.example.com",
+            "example..com",
+            "-example.com",
+            "example-.com",
+            "https://example.com",
+            "example.com/path",
+        ] {
+            assert!(normalize_pattern(pattern).is_err(), "{}", pattern);
+        }
+
+        assert!(normalize_pattern(&"a".repeat(254)).is_err());
+    }
+
+    #[test]
+    fn patterns_match_the_domain_and_its_subdomains() {
+        let rule = rule("example.com", DomainRuleKind::Deny);
+
+        assert!(rule.matches("example.com"));
+        assert!(rule.matches("EXAMPLE.com."));
+        assert!(rule.matches("www.example.com"));
+        assert!(rule.matches("a.b.example.com"));
+
+        assert!(!rule.matches("badexample.com"));
+        assert!(!rule.matches("example.com.evil.com"));
+        assert!(!rule.matches("example.co"));
+        assert!(!rule.matches("com"));
+    }
+
+    #[test]
+    fn anything_goes_without_rules() {
+        assert!(check_domain(&[], "example.com").is_ok());
+    }
+
+    #[test]
+    fn denied_domains_are_rejected() {
+        let rules = [rule("evil.com", DomainRuleKind::Deny)];
+
+        assert!(matches!(
+            check_domain(&rules, "www.Evil.com"),
+            Err(UrlError::DomainDenied { domain }) if domain == "www.evil.com"
+        ));
+        assert!(check_domain(&rules, "example.com").is_ok());
+    }
+
+    #[test]
+    fn allowing_a_domain_rejects_all_others() {
+        let rules = [
+            rule("example.com", DomainRuleKind::Allow),
+            rule("example.org", DomainRuleKind::Allow),
+        ];
+
+        assert!(check_domain(&rules, "example.com").is_ok());
+        assert!(check_domain(&rules, "docs.example.org").is_ok());
+        assert!(matches!(
+            check_domain(&rules, "example.net"),
+            Err(UrlError::DomainNotAllowed { domain }) if domain == "example.net"
+        ));
+    }
+
+    #[test]
+    fn denying_wins_over_allowing() {
+        let rules = [
+            rule("example.com", DomainRuleKind::Allow),
+            rule("private.example.com", DomainRuleKind::Deny),
+        ];
+
+        assert!(check_domain(&rules, "www.example.com").is_ok());
+        assert!(matches!(
+            check_domain(&rules, "x.private.example.com"),
+            Err(UrlError::DomainDenied { .. })
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+use rand::Rng;
+
+/// Digits and lowercase letters. Keys are case-insensitive, so uppercase
+/// letters would only repeat these.
+pub const BASE36_ALPHABET: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+const LOOKALIKE_CHARACTERS: &str = "01ilo";
+
+pub type KeyGeneratorRef = std::sync::Arc<KeyGenerator>;
+
+pub struct KeyGenerator {
+    characters: Vec<char>,
+    length: usize,
+    max_attempts: usize,
+}
+
+impl KeyGenerator {
+    pub fn new(
+        alphabet: &str,
+        length: usize,
+        max_attempts: usize,
+        exclude_lookalikes: bool,
+    ) -> Result<KeyGenerator, String> {
+        let mut characters: Vec<char> = vec![];
+
+        // Keys are case-insensitive, so characters that only differ in case
+        // are the same character, and would be picked more often
+        for c in alphabet.chars().map(|c| c.to_ascii_lowercase()) {
+            // Anything else would need percent-encoding in a path, or changes
+            // what the path means, such as a trailing `+` asking for a preview
+            if !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '~') {
+                return Err(format!(
+                    "Key generator alphabet can only contain letters, digits, '-', '_' and '~', found {c:?}"
+                ));
+            }
+
+            if characters.contains(&c) {
+                return Err(format!(
+                    "Key generator alphabet contains {c:?} more than once, ignoring case"
+                ));
+            }
+
+            characters.push(c);
+        }
+
+        if exclude_lookalikes {
+            characters.retain(|c| !LOOKALIKE_CHARACTERS.contains(*c));
+        }
+
+        if characters.is_empty() {
+            return Err(String::from(
+                "Key generator alphabet must contain at least one usable character",
+            ));
+        }
+
+        if length == 0 {
+            return Err(String::from("Key generator length must be greater than 0"));
+        }
+
+        return Ok(KeyGenerator {
+            characters,
+            length,
+            max_attempts: max_attempts.max(1),
+        });
+    }
+
+    pub fn generate(&self) -> String {
+        let mut rng = rand::thread_rng();
+
+        return (0..self.length)
+            .map(|_| self.characters[rng.gen_range(0..self.characters.len())])
+            .collect();
+    }
+
+    pub fn max_attempts(&self) -> usize {
+        return self.max_attempts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyGenerator, BASE36_ALPHABET};
+
+    #[test]
+    fn rejects_characters_that_are_not_path_safe() {
+        for c in [" ", "/", "?", "#", "%", ".", "+", "é"] {
+            let alphabet = format!("abc{c}");
+
+            assert!(KeyGenerator::new(&alphabet, 6, 10, false).is_err(), "{c:?}");
+        }
+
+        assert!(KeyGenerator::new("abc-_~", 6, 10, false).is_ok());
+    }
+
+    #[test]
+    fn rejects_characters_repeated_in_another_case() {
+        assert!(KeyGenerator::new("abcA", 6, 10, false).is_err());
+    }
+
+    #[test]
+    fn rejects_alphabets_left_empty_and_zero_length() {
+        assert!(KeyGenerator::new("", 6, 10, false).is_err());
+        assert!(KeyGenerator::new("01ilo", 6, 10, true).is_err());
+        assert!(KeyGenerator::new(BASE36_ALPHABET, 0, 10, false).is_err());
+    }
+
+    #[test]
+    fn generates_lowercase_keys_from_the_alphabet() {
+        let generator = KeyGenerator::new("ABC", 8, 10, false).unwrap();
+        let key = generator.generate();
+
+        assert_eq!(key.len(), 8);
+        assert!(key.chars().all(|c| "abc".contains(c)));
+    }
+
+    #[test]
+    fn leaves_out_lookalikes_when_asked() {
+        let generator = KeyGenerator::new(BASE36_ALPHABET, 64, 10, true).unwrap();
+
+        assert!(!generator.generate().chars().any(|c| "01ilo".contains(c)));
+    }
+}
@@ -0,0 +1,267 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use lru::LruCache;
+
+use super::types::lockout::LockoutStatus;
+
+pub type LockoutTrackerRef = std::sync::Arc<LockoutTracker>;
+
+struct LockoutEntry {
+    failures: u32,
+    lockouts: u32,
+    last_failure_at: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// Tracks consecutive failures by identifier and locks an identifier out
+/// after too many of them. Every further lockout lasts twice as long as the
+/// one before, up to `max_lockout`. An identifier that hasn't failed for
+/// `max_lockout` starts over. Only the most recently seen identifiers are
+/// remembered, but locked out ones are kept over any other.
+pub struct LockoutTracker {
+    entries: Mutex<LruCache<String, LockoutEntry>>,
+    max_failures: u32,
+    base_lockout: Duration,
+    max_lockout: Duration,
+}
+
+impl LockoutTracker {
+    pub fn new(
+        capacity: NonZeroUsize,
+        max_failures: u32,
+        base_lockout: Duration,
+        max_lockout: Duration,
+    ) -> LockoutTracker {
+        return LockoutTracker {
+            entries: Mutex::new(LruCache::new(capacity)),
+            max_failures,
+            base_lockout,
+            max_lockout: max_lockout.max(base_lockout),
+        };
+    }
+
+    /// Time left until the identifier may try again, if it is locked out.
+    pub fn retry_after(&self, id: &str) -> Option<Duration> {
+        let mut entries = self.entries.lock().unwrap();
+
+        let locked_until = entries.get(id).and_then(|entry| entry.locked_until)?;
+
+        return locked_until.checked_duration_since(Instant::now());
+    }
+
+    /// Counts a failure, returning how long the identifier is now locked out
+    /// for if this failure locked it.
+    pub fn record_failure(&self, id: &str) -> Option<Duration> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+
+        let stale = entries
+            .peek(id)
+            .is_none_or(|entry| self.is_stale(entry, now));
+
+        if stale {
+            if !entries.contains(id) {
+                make_room(&mut entries, now);
+            }
+
+            entries.put(
+                id.to_string(),
+                LockoutEntry {
+                    failures: 0,
+                    lockouts: 0,
+                    last_failure_at: now,
+                    locked_until: None,
+                },
+            );
+        }
+
+        let entry = entries.get_mut(id).unwrap();
+
+        entry.failures += 1;
+        entry.last_failure_at = now;
+
+        if entry.failures < self.max_failures {
+            return None;
+        }
+
+        let lockout = self
+            .base_lockout
+            .checked_mul(2u32.saturating_pow(entry.lockouts))
+            .unwrap_or(self.max_lockout)
+            .min(self.max_lockout);
+
+        entry.failures = 0;
+        entry.lockouts += 1;
+        entry.locked_until = Some(now + lockout);
+
+        return Some(lockout);
+    }
+
+    pub fn status(&self, id: &str) -> Option<LockoutStatus> {
+        let entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+
+        let entry = entries
+            .peek(id)
+            .filter(|entry| !self.is_stale(entry, now))?;
+
+        let locked_until = entry
+            .locked_until
+            .and_then(|locked_until| locked_until.checked_duration_since(now))
+            .and_then(|remaining| chrono::Duration::from_std(remaining).ok())
+            .map(|remaining| Utc::now() + remaining);
+
+        return Some(LockoutStatus {
+            failures: entry.failures,
+            lockouts: entry.lockouts,
+            locked_until,
+        });
+    }
+
+    /// Forgets the identifier's failures and lifts its lockout. Returns
+    /// whether there was anything to forget.
+    pub fn clear(&self, id: &str) -> bool {
+        return self.entries.lock().unwrap().pop(id).is_some();
+    }
+
+    fn is_stale(&self, entry: &LockoutEntry, now: Instant) -> bool {
+        let quiet_since = match entry.locked_until {
+            Some(locked_until) if locked_until > entry.last_failure_at => locked_until,
+            _ => entry.last_failure_at,
+        };
+
+        return now.saturating_duration_since(quiet_since) >= self.max_lockout;
+    }
+}
+
+/// Evicts the least recently seen identifier that isn't locked out, so that
+/// failing for many other identifiers can't push a lockout out of the cache
+/// and lift it early. Only when every identifier is locked out does the least
+/// recently seen one go.
+fn make_room(entries: &mut LruCache<String, LockoutEntry>, now: Instant) {
+    if entries.len() < entries.cap().get() {
+        return;
+    }
+
+    let unlocked = entries
+        .iter()
+        .rev()
+        .find(|(_, entry)| {
+            entry
+                .locked_until
+                .is_none_or(|locked_until| locked_until <= now)
+        })
+        .map(|(id, _)| id.clone());
+
+    match unlocked {
+        Some(id) => {
+            entries.pop(&id);
+        }
+        None => {
+            entries.pop_lru();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    use super::LockoutTracker;
+
+    fn tracker(capacity: usize, base_lockout: Duration, max_lockout: Duration) -> LockoutTracker {
+        return LockoutTracker::new(
+            NonZeroUsize::new(capacity).unwrap(),
+            2,
+            base_lockout,
+            max_lockout,
+        );
+    }
+
+    #[test]
+    fn locks_out_after_max_failures() {
+        let tracker = tracker(10, Duration::from_secs(60), Duration::from_secs(600));
+
+        assert_eq!(tracker.record_failure("a"), None);
+        assert_eq!(tracker.retry_after("a"), None);
+
+        assert_eq!(tracker.record_failure("a"), Some(Duration::from_secs(60)));
+        assert!(tracker.retry_after("a").is_some());
+        assert_eq!(tracker.retry_after("b"), None);
+    }
+
+    #[test]
+    fn doubles_each_lockout_up_to_the_maximum() {
+        let tracker = tracker(10, Duration::from_secs(60), Duration::from_secs(200));
+
+        let lockouts: Vec<_> = (0..8).filter_map(|_| tracker.record_failure("a")).collect();
+
+        assert_eq!(
+            lockouts,
+            [60, 120, 200, 200].map(Duration::from_secs).to_vec()
+        );
+
+        let status = tracker.status("a").unwrap();
+        assert_eq!(status.failures, 0);
+        assert_eq!(status.lockouts, 4);
+        assert!(status.locked_until.is_some());
+    }
+
+    #[test]
+    fn lockouts_expire() {
+        let tracker = tracker(10, Duration::from_millis(20), Duration::from_secs(60));
+
+        tracker.record_failure("a");
+        tracker.record_failure("a");
+        assert!(tracker.retry_after("a").is_some());
+
+        sleep(Duration::from_millis(40));
+
+        assert_eq!(tracker.retry_after("a"), None);
+    }
+
+    #[test]
+    fn starts_over_after_a_quiet_max_lockout() {
+        let tracker = tracker(10, Duration::from_millis(10), Duration::from_millis(20));
+
+        tracker.record_failure("a");
+        tracker.record_failure("a");
+
+        sleep(Duration::from_millis(50));
+
+        assert!(tracker.status("a").is_none());
+        assert_eq!(tracker.record_failure("a"), None);
+        assert_eq!(tracker.record_failure("a"), Some(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn keeps_lockouts_over_other_identifiers() {
+        let tracker = tracker(2, Duration::from_secs(60), Duration::from_secs(600));
+
+        tracker.record_failure("locked");
+        tracker.record_failure("locked");
+
+        for i in 0..10 {
+            tracker.record_failure(&format!("other-{i}"));
+        }
+
+        assert!(tracker.retry_after("locked").is_some());
+    }
+
+    #[test]
+    fn clear_lifts_a_lockout() {
+        let tracker = tracker(10, Duration::from_secs(60), Duration::from_secs(600));
+
+        tracker.record_failure("a");
+        tracker.record_failure("a");
+
+        assert!(tracker.clear("a"));
+        assert_eq!(tracker.retry_after("a"), None);
+        assert!(!tracker.clear("a"));
+    }
+}
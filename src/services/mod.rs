@@ -1,4 +1,14 @@
+pub mod audit;
+pub mod cache;
+pub mod click;
+pub mod domain;
+pub mod key;
+pub mod lockout;
 pub mod password;
+pub mod qr;
+pub mod rate_limit;
+pub mod template;
+pub mod token;
 pub mod types;
 pub mod url;
 pub mod user;
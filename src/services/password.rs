@@ -6,6 +6,9 @@ pub trait PasswordService: Send + Sync {
     fn generate_hash(&self, client_secret: &str) -> Result<String, UserError>;
 
     fn verify_client_secret(&self, hash: &str, client_secret: &str) -> Result<bool, UserError>;
+
+    /// Whether the hash was made with other parameters than new hashes are.
+    fn needs_rehash(&self, hash: &str) -> bool;
 }
 
 pub type Argon2Config = argon2::Config<'static>;
@@ -33,4 +36,89 @@ impl PasswordService for Argon2PasswordService {
         return argon2::verify_encoded_ext(hash, client_secret.as_bytes(), self.config.secret, &[])
             .map_err(|e| UserError::HashError(e.to_string()));
     }
+
+    fn needs_rehash(&self, hash: &str) -> bool {
+        let config = &self.config;
+
+        // Encoded hashes look like `$argon2id$v=19$m=4096,t=3,p=1$<salt>$<hash>`
+        let params = format!(
+            "${}$v={}$m={},t={},p={}$",
+            config.variant, config.version, config.mem_cost, config.time_cost, config.lanes
+        );
+
+        // Unpadded base64 length of the hash
+        let hash_length = (config.hash_length as usize * 4).div_ceil(3);
+
+        return !hash.starts_with(&params)
+            || hash.rsplit('$').next().map(str::len) != Some(hash_length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Argon2Config, Argon2ConfigRef, Argon2PasswordService, PasswordService};
+
+    fn service(config: Argon2Config) -> Box<dyn PasswordService> {
+        return Argon2PasswordService::new(Argon2ConfigRef::new(config));
+    }
+
+    fn cheap_config() -> Argon2Config {
+        return Argon2Config {
+            mem_cost: 64,
+            time_cost: 1,
+            lanes: 1,
+            hash_length: 32,
+            ..Argon2Config::default()
+        };
+    }
+
+    #[test]
+    fn hashes_made_with_the_current_parameters_are_kept() {
+        let service = service(cheap_config());
+        let hash = service.generate_hash("secret").unwrap();
+
+        assert!(!service.needs_rehash(&hash));
+    }
+
+    #[test]
+    fn hashes_made_with_other_parameters_need_rehashing() {
+        let hash = service(cheap_config()).generate_hash("secret").unwrap();
+
+        for config in [
+            Argon2Config {
+                mem_cost: 128,
+                ..cheap_config()
+            },
+            Argon2Config {
+                time_cost: 2,
+                ..cheap_config()
+            },
+            Argon2Config {
+                lanes: 2,
+                ..cheap_config()
+            },
+            Argon2Config {
+                hash_length: 16,
+                ..cheap_config()
+            },
+            Argon2Config {
+                variant: argon2::Variant::Argon2id,
+                ..cheap_config()
+            },
+        ] {
+            let service = service(config);
+
+            assert!(service.needs_rehash(&hash));
+            // Old hashes still verify until they are replaced
+            assert!(service.verify_client_secret(&hash, "secret").unwrap());
+        }
+    }
+
+    #[test]
+    fn unreadable_hashes_need_rehashing() {
+        let service = service(cheap_config());
+
+        assert!(service.needs_rehash(""));
+        assert!(service.needs_rehash("not a hash"));
+    }
 }
@@ -0,0 +1,126 @@
+use percent_encoding::utf8_percent_encode;
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::errors::url::UrlError;
+
+use super::template::COMPONENT;
+use super::types::qr::{QrErrorCorrection, QrFormat, QrImage, QrOptions};
+
+pub const MIN_SIZE: u32 = 64;
+pub const MAX_SIZE: u32 = 4096;
+pub const MAX_MARGIN: u32 = 32;
+
+/// Public short URL of a key, with each path segment of the key encoded.
+pub fn short_url(base_url: &str, key: &str) -> String {
+    let path: Vec<String> = key
+        .split('/')
+        .map(|segment| utf8_percent_encode(segment, COMPONENT).to_string())
+        .collect();
+
+    return format!("{}/{}", base_url, path.join("/"));
+}
+
+/// Renders a QR code encoding `data`. Modules are scaled by a whole number of
+/// pixels, so PNG images may come out slightly smaller than the requested size.
+pub fn render(data: &str, options: &QrOptions) -> Result<QrImage, UrlError> {
+    if options.size < MIN_SIZE || options.size > MAX_SIZE {
+        return Err(UrlError::QrSizeInvalid {
+            min: MIN_SIZE,
+            max: MAX_SIZE,
+        });
+    }
+
+    if options.margin > MAX_MARGIN {
+        return Err(UrlError::QrMarginInvalid { max: MAX_MARGIN });
+    }
+
+    let ec_level = match options.error_correction {
+        QrErrorCorrection::Low => EcLevel::L,
+        QrErrorCorrection::Medium => EcLevel::M,
+        QrErrorCorrection::Quartile => EcLevel::Q,
+        QrErrorCorrection::High => EcLevel::H,
+    };
+
+    let code = QrCode::with_error_correction_level(data, ec_level).map_err(|e| {
+        error!("Failed to encode QR code for {}: {:?}", data, e);
+        return UrlError::QrGenerationFailed;
+    })?;
+
+    let width = code.width() as u32;
+    let dark: Vec<bool> = code
+        .to_colors()
+        .into_iter()
+        .map(|color| color == Color::Dark)
+        .collect();
+
+    let bytes = match options.format {
+        QrFormat::Png => render_png(&dark, width, options)?,
+        QrFormat::Svg => render_svg(&dark, width, options),
+    };
+
+    return Ok(QrImage {
+        format: options.format,
+        bytes,
+    });
+}
+
+fn render_png(dark: &[bool], width: u32, options: &QrOptions) -> Result<Vec<u8>, UrlError> {
+    let modules = width + 2 * options.margin;
+    let scale = (options.size / modules).max(1);
+    let pixels = modules * scale;
+
+    let mut data = vec![255u8; (pixels * pixels) as usize];
+
+    for (i, _) in dark.iter().enumerate().filter(|(_, dark)| **dark) {
+        let x = (i as u32 % width + options.margin) * scale;
+        let y = (i as u32 / width + options.margin) * scale;
+
+        for row in y..y + scale {
+            let start = (row * pixels + x) as usize;
+            data[start..start + scale as usize].fill(0);
+        }
+    }
+
+    let mut bytes = vec![];
+
+    let mut encoder = png::Encoder::new(&mut bytes, pixels, pixels);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|e| {
+            error!("Failed to encode QR code as PNG: {:?}", e);
+            return UrlError::QrGenerationFailed;
+        })?;
+
+    return Ok(bytes);
+}
+
+fn render_svg(dark: &[bool], width: u32, options: &QrOptions) -> Vec<u8> {
+    let modules = width + 2 * options.margin;
+
+    let mut path = String::new();
+
+    for (i, _) in dark.iter().enumerate().filter(|(_, dark)| **dark) {
+        let x = i as u32 % width + options.margin;
+        let y = i as u32 / width + options.margin;
+
+        path.push_str(&format!("M{},{}h1v1h-1z", x, y));
+    }
+
+    let svg = format!(
+        r##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {modules} {modules}" shape-rendering="crispEdges">
+<rect width="{modules}" height="{modules}" fill="#fff"/>
+<path fill="#000" d="{path}"/>
+</svg>
+"##,
+        size = options.size,
+        modules = modules,
+        path = path
+    );
+
+    return svg.into_bytes();
+}
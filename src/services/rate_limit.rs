@@ -0,0 +1,88 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+
+use super::types::rate_limit::RateLimitState;
+
+pub type RateLimiterRef = std::sync::Arc<RateLimiter>;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Token buckets by identifier. Each bucket holds up to `capacity` requests
+/// and refills continuously at `per_minute`. Only the most recently seen
+/// identifiers are remembered, and a forgotten one starts with a full bucket.
+pub struct RateLimiter {
+    buckets: Mutex<LruCache<String, Bucket>>,
+    capacity: u32,
+    per_second: f64,
+}
+
+impl RateLimiter {
+    pub fn new(entries: NonZeroUsize, capacity: u32, per_minute: u32) -> RateLimiter {
+        return RateLimiter {
+            buckets: Mutex::new(LruCache::new(entries)),
+            capacity,
+            per_second: per_minute as f64 / 60.0,
+        };
+    }
+
+    /// Takes one request from the identifier's bucket, if it has any left.
+    pub fn take(&self, id: &str) -> RateLimitState {
+        return self.update(id, true);
+    }
+
+    /// Reports the identifier's bucket without taking from it.
+    pub fn peek(&self, id: &str) -> RateLimitState {
+        return self.update(id, false);
+    }
+
+    fn update(&self, id: &str, take: bool) -> RateLimitState {
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = Instant::now();
+        let capacity = self.capacity as f64;
+
+        if buckets.peek(id).is_none() {
+            buckets.put(
+                id.to_string(),
+                Bucket {
+                    tokens: capacity,
+                    updated_at: now,
+                },
+            );
+        }
+
+        let bucket = buckets.get_mut(id).unwrap();
+
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(capacity);
+        bucket.updated_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+
+        if allowed && take {
+            bucket.tokens -= 1.0;
+        }
+
+        let retry_after = if allowed {
+            None
+        } else {
+            Some(self.time_to_refill(1.0 - bucket.tokens))
+        };
+
+        return RateLimitState {
+            limit: self.capacity,
+            remaining: bucket.tokens.floor() as u32,
+            reset_after: self.time_to_refill(capacity - bucket.tokens),
+            retry_after,
+        };
+    }
+
+    fn time_to_refill(&self, tokens: f64) -> Duration {
+        return Duration::from_secs_f64(tokens.max(0.0) / self.per_second);
+    }
+}
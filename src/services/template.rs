@@ -0,0 +1,269 @@
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::errors::url::UrlError;
+
+/// Everything but unreserved characters, so that a substituted value can
+/// never change the structure of the URL around it.
+pub const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placeholder {
+    /// `{1}`, `{2}`, ...: a single segment of the unmatched path
+    Segment(usize),
+    /// `{path}`: the whole unmatched path
+    Path,
+    /// `{query.name}`: the value of a query parameter
+    Query(String),
+}
+
+impl Placeholder {
+    fn parse(name: &str) -> Option<Placeholder> {
+        if name == "path" {
+            return Some(Self::Path);
+        }
+
+        if let Some(parameter) = name.strip_prefix("query.") {
+            if parameter.is_empty() {
+                return None;
+            }
+
+            return Some(Self::Query(String::from(parameter)));
+        }
+
+        return match name.parse::<usize>() {
+            Ok(index) if index > 0 => Some(Self::Segment(index)),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// Destination URL with placeholders filled from the request at redirect time.
+#[derive(Debug, Clone)]
+pub struct UrlTemplate {
+    parts: Vec<Part>,
+}
+
+impl UrlTemplate {
+    /// Returns `None` when the URL has no placeholders.
+    pub fn parse(url: &str) -> Result<Option<UrlTemplate>, UrlError> {
+        if !url.contains('{') && !url.contains('}') {
+            return Ok(None);
+        }
+
+        let mut parts = vec![];
+        let mut rest = url;
+
+        while let Some(start) = rest.find(['{', '}']) {
+            if rest[start..].starts_with('}') {
+                return Err(UrlError::TemplateInvalid {
+                    placeholder: String::from("}"),
+                });
+            }
+
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => {
+                    return Err(UrlError::TemplateInvalid {
+                        placeholder: String::from(&rest[start..]),
+                    })
+                }
+            };
+
+            let name = &rest[start + 1..end];
+
+            let placeholder =
+                Placeholder::parse(name).ok_or_else(|| UrlError::TemplateInvalid {
+                    placeholder: format!("{{{name}}}"),
+                })?;
+
+            if start > 0 {
+                parts.push(Part::Literal(String::from(&rest[..start])));
+            }
+
+            parts.push(Part::Placeholder(placeholder));
+
+            rest = &rest[end + 1..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Literal(String::from(rest)));
+        }
+
+        return Ok(Some(UrlTemplate { parts }));
+    }
+
+    /// Whether any placeholder is filled from the path, which only prefix links have.
+    pub fn uses_path(&self) -> bool {
+        return self.parts.iter().any(|part| {
+            matches!(
+                part,
+                Part::Placeholder(Placeholder::Segment(_) | Placeholder::Path)
+            )
+        });
+    }
+
+    /// Fills every placeholder with the same raw value, used to validate the
+    /// URLs a template can produce.
+    pub fn expand_with(&self, value: &str) -> String {
+        return self.render(|_| String::from(value));
+    }
+
+    /// Fills placeholders from the unmatched path segments and the request
+    /// query. Missing values become empty, and every value is percent-encoded.
+    pub fn expand(&self, remainder: &[String], query: Option<&str>) -> String {
+        return self.render(|placeholder| match placeholder {
+            Placeholder::Segment(index) => remainder
+                .get(index - 1)
+                .map(|segment| encode(segment))
+                .unwrap_or_default(),
+            Placeholder::Path => remainder
+                .iter()
+                .map(|segment| encode(segment))
+                .collect::<Vec<String>>()
+                .join("/"),
+            Placeholder::Query(name) => query
+                .and_then(|query| {
+                    url::form_urlencoded::parse(query.as_bytes())
+                        .find(|(key, _)| key == name.as_str())
+                        .map(|(_, value)| encode(&value))
+                })
+                .unwrap_or_default(),
+        });
+    }
+
+    fn render<F>(&self, value_of: F) -> String
+    where
+        F: Fn(&Placeholder) -> String,
+    {
+        let mut rendered = String::new();
+
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => rendered.push_str(literal),
+                Part::Placeholder(placeholder) => rendered.push_str(&value_of(placeholder)),
+            }
+        }
+
+        return rendered;
+    }
+}
+
+fn encode(value: &str) -> String {
+    return utf8_percent_encode(value, COMPONENT).to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UrlTemplate;
+    use crate::errors::url::UrlError;
+
+    fn template(url: &str) -> UrlTemplate {
+        return UrlTemplate::parse(url).unwrap().unwrap();
+    }
+
+    fn invalid_placeholder(url: &str) -> String {
+        return match UrlTemplate::parse(url) {
+            Err(UrlError::TemplateInvalid { placeholder }) => placeholder,
+            other => panic!("expected an invalid template, got {:?}", other),
+        };
+    }
+
+    fn segments(segments: &[&str]) -> Vec<String> {
+        return segments
+            .iter()
+            .map(|segment| String::from(*segment))
+            .collect();
+    }
+
+    #[test]
+    fn urls_without_placeholders_are_not_templates() {
+        assert!(UrlTemplate::parse("https://example.com/a?b=c")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_unbalanced_braces() {
+        assert_eq!(invalid_placeholder("https://example.com/{1"), "{1");
+        assert_eq!(invalid_placeholder("https://example.com/1}"), "}");
+        assert_eq!(invalid_placeholder("https://example.com/}{1}"), "}");
+        assert_eq!(invalid_placeholder("https://example.com/{{1}}"), "{{1}");
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders() {
+        assert_eq!(invalid_placeholder("https://example.com/{0}"), "{0}");
+        assert_eq!(invalid_placeholder("https://example.com/{-1}"), "{-1}");
+        assert_eq!(invalid_placeholder("https://example.com/{}"), "{}");
+        assert_eq!(invalid_placeholder("https://example.com/{name}"), "{name}");
+        assert_eq!(
+            invalid_placeholder("https://example.com/{query.}"),
+            "{query.}"
+        );
+    }
+
+    #[test]
+    fn only_path_placeholders_use_the_path() {
+        assert!(template("https://example.com/{1}").uses_path());
+        assert!(template("https://example.com/{path}").uses_path());
+        assert!(!template("https://example.com/?q={query.q}").uses_path());
+    }
+
+    #[test]
+    fn expands_segments_path_and_query() {
+        let template = template("https://example.com/{2}/{1}?all={path}&q={query.q}");
+
+        assert_eq!(
+            template.expand(&segments(&["a", "b"]), Some("q=c&r=d")),
+            "https://example.com/b/a?all=a/b&q=c"
+        );
+    }
+
+    #[test]
+    fn missing_values_expand_empty() {
+        let template = template("https://example.com/{3}?q={query.q}");
+
+        assert_eq!(
+            template.expand(&segments(&["a"]), None),
+            "https://example.com/?q="
+        );
+    }
+
+    #[test]
+    fn encodes_values_so_they_cant_change_the_url() {
+        let template = template("https://example.com/{1}?q={query.q}");
+
+        assert_eq!(
+            template.expand(&segments(&["a?b#c"]), Some("q=x%26y%3Dz%20w")),
+            "https://example.com/a%3Fb%23c?q=x%26y%3Dz%20w"
+        );
+        assert_eq!(
+            template.expand(&segments(&[".."]), None),
+            "https://example.com/..?q="
+        );
+        assert_eq!(
+            template.expand(&segments(&["é"]), None),
+            "https://example.com/%C3%A9?q="
+        );
+    }
+
+    #[test]
+    fn expand_with_fills_every_placeholder_raw() {
+        let template = template("https://example.com/{1}/{path}?q={query.q}");
+
+        assert_eq!(
+            template.expand_with("a/b"),
+            "https://example.com/a/b/a/b?q=a/b"
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use rocket_sync_db_pools::postgres::Row;
+use sha2::{Digest, Sha256};
+
+use crate::config::database::DbConnection;
+use crate::errors::token::TokenError;
+
+use super::types::{
+    token::{CreateTokenRequest, CreatedToken, Scope, Token},
+    user::User,
+};
+
+const TOKEN_PREFIX: &str = "ulk_";
+const TOKEN_LENGTH: usize = 40;
+const DISPLAY_PREFIX_LENGTH: usize = 12;
+
+/// How stale `last_used_at` may get before a request records it again, so
+/// that busy tokens don't write on every request
+const LAST_USED_RESOLUTION_SECONDS: i64 = 60;
+
+#[rocket::async_trait]
+pub trait TokenService: Send + Sync {
+    async fn create(
+        &self,
+        user: User,
+        token: CreateTokenRequest,
+    ) -> Result<CreatedToken, TokenError>;
+
+    async fn get_all_for_user(&self, user: User) -> Result<Vec<Token>, TokenError>;
+
+    async fn delete_by_id_for_user(&self, user: User, id: i32) -> Result<(), TokenError>;
+
+    /// Resolves an unexpired token to its owner, recording when it was last
+    /// used to within a minute.
+    async fn verify_and_get(&self, secret: String) -> Result<(User, Token), TokenError>;
+}
+
+pub struct DbTokenService {
+    db: DbConnection,
+}
+
+impl DbTokenService {
+    pub fn new(db: DbConnection) -> Self {
+        return Self { db };
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), TokenError> {
+    const MIN: usize = 1;
+    const MAX: usize = 128;
+
+    let length = name.len();
+
+    if length < MIN {
+        return Err(TokenError::NameTooShort { min: MIN });
+    }
+
+    if length > MAX {
+        return Err(TokenError::NameTooLong { max: MAX });
+    }
+
+    return Ok(());
+}
+
+fn generate_secret() -> String {
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect();
+
+    return format!("{TOKEN_PREFIX}{random}");
+}
+
+/// Tokens are long and random, so a fast digest is enough to protect them at rest.
+fn hash_secret(secret: &str) -> String {
+    let mut hasher = Sha256::new();
+
+    hasher.update(secret.as_bytes());
+
+    return format!("{:x}", hasher.finalize());
+}
+
+fn row_to_token(row: &Row) -> Token {
+    let id: i32 = row.get("id");
+    let user_id: i32 = row.get("user_id");
+
+    let value: &str = row.get("name");
+    let name = String::from(value);
+
+    let value: &str = row.get("prefix");
+    let prefix = String::from(value);
+
+    let values: Vec<String> = row.get("scopes");
+    let scopes = values
+        .iter()
+        .filter_map(|value| Scope::from_str(value))
+        .collect();
+
+    let created_at: DateTime<Utc> = row.get("created_at");
+    let expires_at: Option<DateTime<Utc>> = row.get("expires_at");
+    let last_used_at: Option<DateTime<Utc>> = row.get("last_used_at");
+
+    return Token {
+        id,
+        user_id,
+        name,
+        prefix,
+        scopes,
+        created_at,
+        expires_at,
+        last_used_at,
+    };
+}
+
+#[rocket::async_trait]
+impl TokenService for DbTokenService {
+    async fn create(
+        &self,
+        user: User,
+        token: CreateTokenRequest,
+    ) -> Result<CreatedToken, TokenError> {
+        validate_name(&token.name)?;
+
+        if token.scopes.is_empty() {
+            return Err(TokenError::ScopesEmpty);
+        }
+
+        if !user.is_admin && token.scopes.contains(&Scope::UsersAdmin) {
+            return Err(TokenError::ScopeNotAllowed {
+                scope: String::from(Scope::UsersAdmin.as_str()),
+            });
+        }
+
+        if let Some(expires_at) = token.expires_at {
+            if expires_at <= Utc::now() {
+                return Err(TokenError::ExpiryInvalid);
+            }
+        }
+
+        let mut scopes: Vec<String> = vec![];
+
+        for scope in token.scopes {
+            let scope = String::from(scope.as_str());
+
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+
+        let secret = generate_secret();
+        let token_hash = hash_secret(&secret);
+        let prefix = String::from(&secret[..DISPLAY_PREFIX_LENGTH]);
+
+        let created = self
+            .db
+            .run(move |connection| {
+                // A duplicate name for the same user fails on the unique constraint
+                for row in connection.query(
+                    "INSERT INTO api_tokens (user_id, name, token_hash, prefix, scopes, expires_at) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id, user_id, name, prefix, scopes, created_at, expires_at, last_used_at;",
+                    &[&user.id, &token.name, &token_hash, &prefix, &scopes, &token.expires_at],
+                )? {
+                    return Ok(row_to_token(&row));
+                }
+
+                return Err(TokenError::Unknown);
+            })
+            .await?;
+
+        return Ok(CreatedToken {
+            token: created,
+            secret,
+        });
+    }
+
+    async fn get_all_for_user(&self, user: User) -> Result<Vec<Token>, TokenError> {
+        return self
+            .db
+            .run(move |connection| {
+                let mut tokens = vec![];
+
+                for row in connection.query(
+                    "SELECT id, user_id, name, prefix, scopes, created_at, expires_at, last_used_at FROM api_tokens WHERE user_id = $1 ORDER BY id ASC;",
+                    &[&user.id],
+                )? {
+                    tokens.push(row_to_token(&row));
+                }
+
+                return Ok(tokens);
+            })
+            .await;
+    }
+
+    async fn delete_by_id_for_user(&self, user: User, id: i32) -> Result<(), TokenError> {
+        return self
+            .db
+            .run(move |connection| {
+                let rows = connection.execute(
+                    "DELETE FROM api_tokens WHERE id = $1 AND user_id = $2;",
+                    &[&id, &user.id],
+                )?;
+
+                if rows != 1 {
+                    return Err(TokenError::NotFound);
+                }
+
+                return Ok(());
+            })
+            .await;
+    }
+
+    async fn verify_and_get(&self, secret: String) -> Result<(User, Token), TokenError> {
+        if !secret.starts_with(TOKEN_PREFIX) {
+            return Err(TokenError::Invalid);
+        }
+
+        let token_hash = hash_secret(&secret);
+
+        return self
+            .db
+            .run(move |connection| {
+                for row in connection.query(
+                    "SELECT api_tokens.id, api_tokens.user_id, api_tokens.name, api_tokens.prefix, api_tokens.scopes, api_tokens.created_at, api_tokens.expires_at, api_tokens.last_used_at, users.client_id, users.is_admin FROM api_tokens INNER JOIN users ON users.id = api_tokens.user_id WHERE api_tokens.token_hash = $1 AND (api_tokens.expires_at IS NULL OR api_tokens.expires_at > NOW()) AND users.deleted_at IS NULL;",
+                    &[&token_hash],
+                )? {
+                    let mut token = row_to_token(&row);
+
+                    let now = Utc::now();
+                    let is_stale = token.last_used_at.is_none_or(|last_used_at| {
+                        now - last_used_at > chrono::Duration::seconds(LAST_USED_RESOLUTION_SECONDS)
+                    });
+
+                    if is_stale {
+                        connection.execute(
+                            "UPDATE api_tokens SET last_used_at = $1 WHERE id = $2;",
+                            &[&now, &token.id],
+                        )?;
+
+                        token.last_used_at = Some(now);
+                    }
+
+                    let value: &str = row.get("client_id");
+                    let client_id = String::from(value);
+
+                    let is_admin: bool = row.get("is_admin");
+
+                    let user = User {
+                        id: token.user_id,
+                        client_id,
+                        is_admin,
+                    };
+
+                    return Ok((user, token));
+                }
+
+                return Err(TokenError::Invalid);
+            })
+            .await;
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use rocket::serde::json::Value;
+
+use super::page::PageRequest;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    UrlCreate,
+    UrlUpdate,
+    UrlDelete,
+    UrlRestore,
+    UrlTransfer,
+    UrlShare,
+    UrlUnshare,
+    UserCreate,
+    UserUpdate,
+    UserDelete,
+    UserRestore,
+    UserUnlock,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            Self::UrlCreate => "url.create",
+            Self::UrlUpdate => "url.update",
+            Self::UrlDelete => "url.delete",
+            Self::UrlRestore => "url.restore",
+            Self::UrlTransfer => "url.transfer",
+            Self::UrlShare => "url.share",
+            Self::UrlUnshare => "url.unshare",
+            Self::UserCreate => "user.create",
+            Self::UserUpdate => "user.update",
+            Self::UserDelete => "user.delete",
+            Self::UserRestore => "user.restore",
+            Self::UserUnlock => "user.unlock",
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditTarget {
+    Url(String),
+    User(i32),
+}
+
+impl AuditTarget {
+    pub fn kind(&self) -> &'static str {
+        return match self {
+            Self::Url(_) => "url",
+            Self::User(_) => "user",
+        };
+    }
+
+    pub fn id(&self) -> String {
+        return match self {
+            Self::Url(key) => key.clone(),
+            Self::User(id) => id.to_string(),
+        };
+    }
+}
+
+/// An entry about to be written. Snapshots never contain secrets.
+#[derive(Debug)]
+pub struct NewAuditEntry {
+    pub actor_id: i32,
+    pub action: AuditAction,
+    pub target: AuditTarget,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Not tied to the users table, so entries outlive deleted users
+    pub actor_id: Option<i32>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct AuditQuery {
+    pub page: PageRequest,
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,30 @@
+use super::{token::Scope, user::User};
+
+/// How the current request was authenticated.
+#[derive(Debug, Clone)]
+pub enum Authentication {
+    ClientCredentials,
+    Token { scopes: Vec<Scope> },
+}
+
+impl Authentication {
+    /// Client credentials grant every scope, tokens only those they were minted with.
+    pub fn allows(&self, scope: Scope) -> bool {
+        return match self {
+            Self::ClientCredentials => true,
+            Self::Token { scopes, .. } => scopes.contains(&scope),
+        };
+    }
+}
+
+/// A user allowed to read URLs.
+#[derive(Debug)]
+pub struct UrlsReader(pub User);
+
+/// A user allowed to create, update and delete URLs.
+#[derive(Debug)]
+pub struct UrlsWriter(pub User);
+
+/// A user authenticated with their client id and secret rather than a token.
+#[derive(Debug)]
+pub struct ClientUser(pub User);
@@ -0,0 +1,7 @@
+#[derive(Debug)]
+pub struct RedirectCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub size: usize,
+    pub capacity: usize,
+}
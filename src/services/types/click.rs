@@ -0,0 +1,29 @@
+use std::net::IpAddr;
+
+#[derive(Debug)]
+pub struct Visitor {
+    pub referrer: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<IpAddr>,
+}
+
+#[derive(Debug)]
+pub struct CreateClickRequest {
+    pub key: String,
+    pub visitor: Visitor,
+}
+
+#[derive(Debug)]
+pub struct DailyClicks {
+    pub date: String,
+    pub clicks: i64,
+    pub unique_visitors: i64,
+}
+
+#[derive(Debug)]
+pub struct ClickStats {
+    pub key: String,
+    pub total_clicks: i64,
+    pub unique_visitors: i64,
+    pub days: Vec<DailyClicks>,
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DomainRuleKind {
+    Allow,
+    Deny,
+}
+
+impl DomainRuleKind {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+        };
+    }
+
+    pub fn from_str(value: &str) -> Option<DomainRuleKind> {
+        return match value {
+            "allow" => Some(Self::Allow),
+            "deny" => Some(Self::Deny),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Debug)]
+pub struct CreateDomainRuleRequest {
+    pub pattern: String,
+    pub kind: DomainRuleKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct DomainRule {
+    pub id: i32,
+    /// A domain, matching itself and all of its subdomains
+    pub pattern: String,
+    pub kind: DomainRuleKind,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DomainRule {
+    pub fn matches(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+
+        return host == self.pattern
+            || host
+                .strip_suffix(&self.pattern)
+                .is_some_and(|subdomain| subdomain.ends_with('.'));
+    }
+}
@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+
+pub struct LockoutStatus {
+    /// Failures since the last lockout
+    pub failures: u32,
+    /// Lockouts since the last success
+    pub lockouts: u32,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+pub struct UserLockout {
+    pub user_id: i32,
+    pub client_id: String,
+    pub failures: u32,
+    pub lockouts: u32,
+    pub locked_until: Option<DateTime<Utc>>,
+}
@@ -1,3 +1,13 @@
 pub mod admin;
+pub mod audit;
+pub mod auth;
+pub mod cache;
+pub mod click;
+pub mod domain;
+pub mod lockout;
+pub mod page;
+pub mod qr;
+pub mod rate_limit;
+pub mod token;
 pub mod url;
 pub mod user;
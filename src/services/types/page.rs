@@ -0,0 +1,64 @@
+pub const DEFAULT_LIMIT: i64 = 50;
+pub const MAX_LIMIT: i64 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_sql(&self) -> &'static str {
+        return match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        };
+    }
+}
+
+/// Requested page of results. The cursor is opaque to clients, and is
+/// produced by a previous `Page::next`.
+#[derive(Debug, Default)]
+pub struct PageRequest {
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+impl PageRequest {
+    pub fn limit(&self) -> i64 {
+        return self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    }
+
+    /// Returns `None` when the cursor isn't one this service produced.
+    pub fn offset(&self) -> Option<i64> {
+        return match &self.cursor {
+            None => Some(0),
+            Some(cursor) => cursor.parse().ok().filter(|offset: &i64| *offset >= 0),
+        };
+    }
+}
+
+#[derive(Debug)]
+pub struct Page<T> {
+    pub values: Vec<T>,
+    pub total: i64,
+    pub next: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn new(values: Vec<T>, total: i64, offset: i64) -> Page<T> {
+        let end = offset + values.len() as i64;
+
+        let next = if end < total && !values.is_empty() {
+            Some(end.to_string())
+        } else {
+            None
+        };
+
+        return Page {
+            values,
+            total,
+            next,
+        };
+    }
+}
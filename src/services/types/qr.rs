@@ -0,0 +1,45 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrFormat {
+    Png,
+    Svg,
+}
+
+/// Share of the code that may be damaged while staying readable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrErrorCorrection {
+    /// About 7%
+    Low,
+    /// About 15%
+    Medium,
+    /// About 25%
+    Quartile,
+    /// About 30%
+    High,
+}
+
+#[derive(Debug)]
+pub struct QrOptions {
+    pub format: QrFormat,
+    /// Width and height of the image, in pixels
+    pub size: u32,
+    pub error_correction: QrErrorCorrection,
+    /// Quiet zone around the code, in modules
+    pub margin: u32,
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        return Self {
+            format: QrFormat::Png,
+            size: 256,
+            error_correction: QrErrorCorrection::Medium,
+            margin: 4,
+        };
+    }
+}
+
+#[derive(Debug)]
+pub struct QrImage {
+    pub format: QrFormat,
+    pub bytes: Vec<u8>,
+}
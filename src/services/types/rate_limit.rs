@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+/// What is left of a rate limit after a request, reported in response headers.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitState {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the bucket is full again
+    pub reset_after: Duration,
+    /// Set when the request was over the limit
+    pub retry_after: Option<Duration>,
+}
+
+/// A visitor within the rate limit for redirects and other anonymous routes.
+#[derive(Debug)]
+pub struct VisitorRateLimit;
@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    UrlsRead,
+    UrlsWrite,
+    UsersAdmin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            Self::UrlsRead => "urls:read",
+            Self::UrlsWrite => "urls:write",
+            Self::UsersAdmin => "users:admin",
+        };
+    }
+
+    pub fn from_str(value: &str) -> Option<Scope> {
+        return match value {
+            "urls:read" => Some(Self::UrlsRead),
+            "urls:write" => Some(Self::UrlsWrite),
+            "users:admin" => Some(Self::UsersAdmin),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Debug)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    /// Leading characters of the token, kept so that users can tell tokens apart
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// A newly minted token. The secret is only ever available at creation.
+#[derive(Debug)]
+pub struct CreatedToken {
+    pub token: Token,
+    pub secret: String,
+}
@@ -1,17 +1,264 @@
+use chrono::{DateTime, Utc};
+
+use crate::errors::url::UrlError;
+use crate::services::template::UrlTemplate;
+
+use super::page::{PageRequest, SortOrder};
+
 #[derive(Debug)]
 pub struct CreateUrlRequest {
-    pub key: String,
+    pub key: Option<String>,
     pub url: String,
+    pub activates_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: Option<u16>,
+    pub is_prefix: Option<bool>,
+    pub passphrase: Option<String>,
+    pub preview_enabled: Option<bool>,
 }
 
 #[derive(Debug)]
 pub struct UpdateUrlRequest {
     pub key: Option<String>,
     pub url: Option<String>,
+    /// `Some(None)` removes the bound
+    pub activates_at: Option<Option<DateTime<Utc>>>,
+    /// `Some(None)` removes the bound
+    pub expires_at: Option<Option<DateTime<Utc>>>,
+    pub redirect_type: Option<u16>,
+    pub is_prefix: Option<bool>,
+    pub passphrase: Option<String>,
+    pub preview_enabled: Option<bool>,
+}
+
+#[derive(Debug)]
+pub struct ImportUrlRequest {
+    pub key: String,
+    pub url: String,
+    pub activates_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: Option<u16>,
+    pub is_prefix: Option<bool>,
+}
+
+/// A row of an import that couldn't be read as a URL
+#[derive(Debug)]
+pub struct UnreadableImportRow {
+    /// The row's key, or empty if it doesn't have one either
+    pub key: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Nothing is created unless every row succeeds.
+    AllOrNothing,
+    /// Valid rows are created, invalid rows are skipped.
+    BestEffort,
+}
+
+#[derive(Debug)]
+pub enum ImportRowStatus {
+    Created,
+    Failed(UrlError),
+    /// The row was valid, but the import was rolled back due to other rows.
+    RolledBack,
 }
 
+#[derive(Debug)]
+pub struct ImportRowResult {
+    /// 1-based position of the row in the submitted file
+    pub row: usize,
+    pub key: String,
+    pub status: ImportRowStatus,
+}
+
+/// A URL deleted together with its owner that stayed deleted when the owner
+/// was restored
+#[derive(Debug)]
+pub struct UnrestoredUrl {
+    pub key: String,
+    pub error: UrlError,
+}
+
+#[derive(Debug)]
+pub struct ImportReport {
+    pub committed: bool,
+    pub rows: Vec<ImportRowResult>,
+}
+
+/// HTTP status used when redirecting to a URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectType {
+    MovedPermanently,
+    Found,
+    SeeOther,
+    TemporaryRedirect,
+    PermanentRedirect,
+}
+
+impl RedirectType {
+    pub const CODES: [u16; 5] = [301, 302, 303, 307, 308];
+
+    pub fn code(&self) -> u16 {
+        return match self {
+            Self::MovedPermanently => 301,
+            Self::Found => 302,
+            Self::SeeOther => 303,
+            Self::TemporaryRedirect => 307,
+            Self::PermanentRedirect => 308,
+        };
+    }
+
+    pub fn from_code(code: u16) -> Option<RedirectType> {
+        return match code {
+            301 => Some(Self::MovedPermanently),
+            302 => Some(Self::Found),
+            303 => Some(Self::SeeOther),
+            307 => Some(Self::TemporaryRedirect),
+            308 => Some(Self::PermanentRedirect),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum UrlWindowState {
+    Pending,
+    Active,
+    Expired,
+}
+
+#[derive(Debug, Clone)]
 pub struct Url {
     pub key: String,
     pub url: String,
     pub user_id: i32,
+    pub activates_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Falls back to the server default when not set
+    pub redirect_type: Option<RedirectType>,
+    /// Also matches longer paths, passing the rest of the path and the query on
+    pub is_prefix: bool,
+    /// Visitors must enter the passphrase before being redirected
+    pub passphrase_hash: Option<String>,
+    /// Anyone may inspect the destination without being redirected
+    pub preview_enabled: bool,
+    pub created_at: DateTime<Utc>,
+    /// Deleted URLs keep their key reserved until they are purged
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// A user other than the owner who may view, update and delete a URL.
+#[derive(Debug, Clone)]
+pub struct UrlCollaborator {
+    pub user_id: i32,
+    pub client_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Url {
+    pub fn window_state(&self) -> UrlWindowState {
+        let now = Utc::now();
+
+        if let Some(activates_at) = self.activates_at {
+            if now < activates_at {
+                return UrlWindowState::Pending;
+            }
+        }
+
+        if let Some(expires_at) = self.expires_at {
+            if now >= expires_at {
+                return UrlWindowState::Expired;
+            }
+        }
+
+        return UrlWindowState::Active;
+    }
+}
+
+/// A URL matched by a request path, with the path segments left unmatched by
+/// its key when it is a prefix link.
+#[derive(Debug)]
+pub struct ResolvedUrl {
+    pub url: Url,
+    pub remainder: Vec<String>,
+}
+
+impl ResolvedUrl {
+    /// Builds the redirect destination. Templates are expanded from the
+    /// remaining path and the request query, otherwise prefix links get the
+    /// remaining path appended and the request query merged into their own.
+    pub fn destination(&self, query: Option<&str>) -> Result<String, UrlError> {
+        let query = query.filter(|query| !query.is_empty());
+
+        let template =
+            UrlTemplate::parse(&self.url.url).map_err(|_| UrlError::UnexpectedUrlParseError)?;
+
+        if let Some(template) = template {
+            return Ok(template.expand(&self.remainder, query));
+        }
+
+        if !self.url.is_prefix || (self.remainder.is_empty() && query.is_none()) {
+            return Ok(self.url.url.clone());
+        }
+
+        let mut destination =
+            url::Url::parse(&self.url.url).map_err(|_| UrlError::UnexpectedUrlParseError)?;
+
+        if !self.remainder.is_empty() {
+            let mut segments = destination
+                .path_segments_mut()
+                .map_err(|_| UrlError::UnexpectedUrlParseError)?;
+
+            segments.pop_if_empty().extend(&self.remainder);
+        }
+
+        if let Some(query) = query {
+            let merged = match destination.query() {
+                Some(existing) if !existing.is_empty() => format!("{existing}&{query}"),
+                _ => String::from(query),
+            };
+
+            destination.set_query(Some(&merged));
+        }
+
+        return Ok(destination.to_string());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlSort {
+    Key,
+    Url,
+    UserId,
+    ActivatesAt,
+    ExpiresAt,
+}
+
+#[derive(Debug)]
+pub struct UrlQuery {
+    pub page: PageRequest,
+    pub sort: UrlSort,
+    pub order: SortOrder,
+    pub key_prefix: Option<String>,
+    pub host: Option<String>,
+    pub user_id: Option<i32>,
+    /// Only URLs with a greater key. Unlike the cursor's offset, it neither
+    /// skips nor repeats URLs created or deleted between pages.
+    pub after_key: Option<String>,
+}
+
+impl Default for UrlQuery {
+    fn default() -> Self {
+        return Self {
+            page: PageRequest::default(),
+            sort: UrlSort::Key,
+            order: SortOrder::Asc,
+            key_prefix: None,
+            host: None,
+            user_id: None,
+            after_key: None,
+        };
+    }
 }
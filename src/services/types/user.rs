@@ -1,3 +1,8 @@
+use chrono::{DateTime, Utc};
+
+use super::page::{PageRequest, SortOrder};
+use super::url::UnrestoredUrl;
+
 #[derive(Debug)]
 pub struct CreateUserRequest {
     pub client_id: String,
@@ -12,9 +17,35 @@ pub struct UpdateUserRequest {
     pub is_admin: Option<bool>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct User {
     pub id: i32,
     pub client_id: String,
     pub is_admin: bool,
 }
+
+#[derive(Debug)]
+pub struct DeletedUser {
+    pub user: User,
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct RestoredUser {
+    pub user: User,
+    pub unrestored_urls: Vec<UnrestoredUrl>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserSort {
+    Id,
+    ClientId,
+}
+
+#[derive(Debug)]
+pub struct UserQuery {
+    pub page: PageRequest,
+    pub sort: UserSort,
+    pub order: SortOrder,
+    pub client_id: Option<String>,
+}
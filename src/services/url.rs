@@ -1,8 +1,29 @@
-use crate::config::database::DbConnection;
-use crate::errors::url::UrlError;
+use chrono::{DateTime, Utc};
+use percent_encoding::percent_decode_str;
+use rocket_sync_db_pools::postgres::{types::ToSql, GenericClient, Row, Transaction};
 
+use crate::config::{
+    app::{KeyLimits, LengthLimits},
+    database::DbConnection,
+};
+use crate::errors::url::UrlError;
+use crate::utils;
+
+use super::audit;
+use super::cache::{RedirectCache, RedirectCacheRef};
+use super::domain;
+use super::key::{KeyGenerator, KeyGeneratorRef};
+use super::password::PasswordService;
+use super::template::UrlTemplate;
 use super::types::{
-    url::{CreateUrlRequest, UpdateUrlRequest, Url},
+    audit::{AuditAction, AuditTarget, NewAuditEntry},
+    domain::DomainRule,
+    page::{Page, PageRequest},
+    url::{
+        CreateUrlRequest, ImportMode, ImportReport, ImportRowResult, ImportRowStatus,
+        ImportUrlRequest, RedirectType, ResolvedUrl, UnreadableImportRow, UnrestoredUrl,
+        UpdateUrlRequest, Url, UrlCollaborator, UrlQuery, UrlSort, UrlWindowState,
+    },
     user::User,
 };
 
@@ -10,17 +31,48 @@ use super::types::{
 pub trait UrlService: Send + Sync {
     async fn create(&self, user: User, url: CreateUrlRequest) -> Result<Url, UrlError>;
 
-    async fn get_all(&self) -> Result<Vec<Url>, UrlError>;
+    /// Creates URLs from the rows of an import. Unreadable rows fail like
+    /// invalid ones.
+    async fn import(
+        &self,
+        user: User,
+        urls: Vec<Result<ImportUrlRequest, UnreadableImportRow>>,
+        mode: ImportMode,
+    ) -> Result<ImportReport, UrlError>;
+
+    async fn get_all(&self, query: UrlQuery) -> Result<Page<Url>, UrlError>;
 
-    async fn get_all_for_user(&self, user: User) -> Result<Vec<Url>, UrlError>;
+    async fn get_all_for_user(&self, user: User, query: UrlQuery) -> Result<Page<Url>, UrlError>;
 
-    async fn get_all_by_user_id(&self, user_id: i32) -> Result<Vec<Url>, UrlError>;
+    async fn get_all_by_user_id(
+        &self,
+        user_id: i32,
+        query: UrlQuery,
+    ) -> Result<Page<Url>, UrlError>;
 
     async fn get_by_key(&self, key: String) -> Result<Url, UrlError>;
 
+    /// Like `get_by_key`, but also returns URLs that aren't active yet or have
+    /// expired, so that callers can explain why they don't redirect.
+    async fn get_by_key_in_any_window(&self, key: String) -> Result<Url, UrlError>;
+
+    /// Resolves a request path to the URL with the longest matching key. Keys
+    /// shorter than the path only match prefix links. Doesn't read the
+    /// redirect cache, callers check `get_cached_by_path` first so that a hit
+    /// needs no connection.
+    async fn get_by_path(&self, path: String) -> Result<ResolvedUrl, UrlError>;
+
+    /// Checks a passphrase against a URL. URLs without one accept any passphrase.
+    async fn verify_passphrase(&self, url: &Url, passphrase: String) -> Result<bool, UrlError>;
+
     async fn get_by_key_for_user(&self, user: User, key: String) -> Result<Url, UrlError>;
 
-    async fn update_by_key(&self, key: String, url: UpdateUrlRequest) -> Result<Url, UrlError>;
+    async fn update_by_key(
+        &self,
+        actor: User,
+        key: String,
+        url: UpdateUrlRequest,
+    ) -> Result<Url, UrlError>;
 
     async fn update_by_key_for_user(
         &self,
@@ -29,53 +81,245 @@ pub trait UrlService: Send + Sync {
         url: UpdateUrlRequest,
     ) -> Result<Url, UrlError>;
 
-    async fn delete_by_key(&self, key: String) -> Result<(), UrlError>;
+    async fn delete_by_key(&self, actor: User, key: String) -> Result<(), UrlError>;
 
     async fn delete_by_key_for_user(&self, user: User, key: String) -> Result<(), UrlError>;
 
-    async fn delete_by_user_id(&self, user_id: i32) -> Result<(), UrlError>;
+    /// Lists deleted URLs that haven't been purged yet, most recently deleted
+    /// first.
+    async fn get_all_deleted(&self, page: PageRequest) -> Result<Page<Url>, UrlError>;
+
+    async fn restore_by_key(&self, actor: User, key: String) -> Result<Url, UrlError>;
+
+    async fn transfer_by_key(
+        &self,
+        actor: User,
+        key: String,
+        user_id: i32,
+    ) -> Result<Url, UrlError>;
+
+    /// Moves every URL of one user to another. Returns how many were moved.
+    async fn transfer_by_user_id(
+        &self,
+        actor: User,
+        from_user_id: i32,
+        to_user_id: i32,
+    ) -> Result<u64, UrlError>;
+
+    /// Lists the users a URL is shared with. Only its owner, its
+    /// collaborators, and admins may see them.
+    async fn get_collaborators(
+        &self,
+        user: User,
+        key: String,
+    ) -> Result<Vec<UrlCollaborator>, UrlError>;
+
+    /// Shares a URL with another user, who may then view, update and delete
+    /// it. Only its owner and admins may share it.
+    async fn add_collaborator(
+        &self,
+        user: User,
+        key: String,
+        collaborator_id: i32,
+    ) -> Result<Vec<UrlCollaborator>, UrlError>;
+
+    async fn remove_collaborator(
+        &self,
+        user: User,
+        key: String,
+        collaborator_id: i32,
+    ) -> Result<(), UrlError>;
+}
+
+/// Settings a `DbUrlService` applies to the URLs it manages
+pub struct UrlSettings {
+    /// Address this service is reachable at, used to detect redirect loops
+    pub public_base_url: Option<url::Url>,
+    /// How long the key of a deleted URL stays reserved
+    pub retention: chrono::Duration,
+    pub key_limits: KeyLimits,
+    pub passphrase_limits: LengthLimits,
 }
 
 pub struct DbUrlService {
     db: DbConnection,
+    key_generator: KeyGeneratorRef,
+    redirect_cache: Option<RedirectCacheRef>,
+    password_service: Box<dyn PasswordService>,
+    /// Address this service is reachable at, used to detect redirect loops
+    public_base_url: Option<url::Url>,
+    /// How long the key of a deleted URL stays reserved
+    retention: chrono::Duration,
+    key_limits: KeyLimits,
+    passphrase_limits: LengthLimits,
 }
 
 impl DbUrlService {
-    pub fn new(db: DbConnection) -> Box<dyn UrlService> {
-        return Box::new(Self { db });
+    pub fn new(
+        db: DbConnection,
+        key_generator: KeyGeneratorRef,
+        redirect_cache: Option<RedirectCacheRef>,
+        password_service: Box<dyn PasswordService>,
+        settings: UrlSettings,
+    ) -> Box<dyn UrlService> {
+        let UrlSettings {
+            public_base_url,
+            retention,
+            key_limits,
+            passphrase_limits,
+        } = settings;
+
+        return Box::new(Self {
+            db,
+            key_generator,
+            redirect_cache,
+            password_service,
+            public_base_url,
+            retention,
+            key_limits,
+            passphrase_limits,
+        });
+    }
+
+    fn hash_passphrase(&self, passphrase: &str) -> Result<String, UrlError> {
+        validate_passphrase(passphrase, &self.passphrase_limits)?;
+
+        return self
+            .password_service
+            .generate_hash(passphrase)
+            .map_err(|e| UrlError::HashError(format!("{:?}", e)));
+    }
+
+    /// An empty passphrase removes the protection from a URL.
+    fn hash_updated_passphrase(
+        &self,
+        passphrase: &Option<String>,
+    ) -> Result<Option<Option<String>>, UrlError> {
+        return match passphrase.as_deref() {
+            Some("") => Ok(Some(None)),
+            Some(passphrase) => Ok(Some(Some(self.hash_passphrase(passphrase)?))),
+            None => Ok(None),
+        };
+    }
+
+    fn invalidate_cached(&self, key: &str) {
+        if let Some(redirect_cache) = &self.redirect_cache {
+            redirect_cache.invalidate(key);
+        }
     }
 }
 
-fn validate_key(key: &str) -> Result<(), UrlError> {
-    const MIN: usize = 1;
-    const MAX: usize = 128;
+const MAX_IMPORT_ROWS: usize = 10_000;
+const MAX_REDIRECT_HOPS: usize = 10;
+
+/// Last path segments that address a sub-resource of a URL in the API, such
+/// as `<key>/stats`. A key with more than one segment can't end in them, or
+/// the API couldn't tell the key apart from the sub-resource.
+const RESERVED_KEY_SUFFIXES: [&str; 4] = ["stats", "qr", "collaborators", "transfer"];
+
+/// Keys that name a route of their own in the API, such as `urls/export`
+const RESERVED_KEYS: [&str; 1] = ["export"];
+
+fn validate_key(key: &str, limits: &KeyLimits) -> Result<(), UrlError> {
+    // Keys are stored lowercased, so that's how the routes will see them
+    let key = key.to_ascii_lowercase();
 
-    if key.starts_with("api/") {
-        return Err(UrlError::KeyReserved {
-            prefix: String::from("/api"),
+    if let Some(reserved) = RESERVED_KEYS
+        .iter()
+        .find(|reserved| key.eq_ignore_ascii_case(reserved))
+    {
+        return Err(UrlError::KeyNameReserved {
+            key: String::from(*reserved),
         });
     }
 
-    if key.starts_with("client/") {
-        return Err(UrlError::KeyReserved {
-            prefix: String::from("/client"),
-        });
+    for prefix in &limits.reserved_prefixes {
+        if key
+            .strip_prefix(prefix.as_str())
+            .is_some_and(|rest| rest.starts_with('/'))
+        {
+            return Err(UrlError::KeyReserved {
+                prefix: format!("/{prefix}"),
+            });
+        }
+    }
+
+    if let Some((_, last)) = key.rsplit_once('/') {
+        if let Some(suffix) = RESERVED_KEY_SUFFIXES
+            .iter()
+            .find(|suffix| last.eq_ignore_ascii_case(suffix))
+        {
+            return Err(UrlError::KeySuffixReserved {
+                suffix: format!("/{suffix}"),
+            });
+        }
+    }
+
+    // Collaborators are also addressed one by one, as `<key>/collaborators/<id>`
+    if let [user_id, parent, _] = key.rsplitn(3, '/').collect::<Vec<_>>()[..] {
+        if parent.eq_ignore_ascii_case("collaborators") && user_id.parse::<i32>().is_ok() {
+            return Err(UrlError::KeySuffixReserved {
+                suffix: String::from("/collaborators/<id>"),
+            });
+        }
     }
 
     let length = key.len();
 
-    if length < MIN {
-        return Err(UrlError::KeyTooShort { min: MIN });
+    if length < limits.min_length {
+        return Err(UrlError::KeyTooShort {
+            min: limits.min_length,
+        });
     }
 
-    if length > MAX {
-        return Err(UrlError::KeyTooLong { max: MAX });
+    if length > limits.max_length {
+        return Err(UrlError::KeyTooLong {
+            max: limits.max_length,
+        });
     }
 
     return Ok(());
 }
 
+/// Validates a destination URL. Templates are validated by the URLs they
+/// expand to, and placeholders may not change the scheme, host or port.
 fn validate_url(raw_url: &str) -> Result<(), UrlError> {
+    let template = match UrlTemplate::parse(raw_url)? {
+        Some(template) => template,
+        None => return validate_destination(raw_url),
+    };
+
+    let first = template.expand_with("a");
+    let second = template.expand_with("b");
+
+    validate_destination(&first)?;
+    validate_destination(&second)?;
+
+    let first = url::Url::parse(&first).map_err(|e| UrlError::UrlParseError(e.to_string()))?;
+    let second = url::Url::parse(&second).map_err(|e| UrlError::UrlParseError(e.to_string()))?;
+
+    if first.scheme() != second.scheme()
+        || first.host_str() != second.host_str()
+        || first.port() != second.port()
+    {
+        return Err(UrlError::TemplateHostNotFixed);
+    }
+
+    return Ok(());
+}
+
+/// Path placeholders are filled from the unmatched path, which only prefix links have.
+fn validate_template_prefix(raw_url: &str, is_prefix: bool) -> Result<(), UrlError> {
+    if let Some(template) = UrlTemplate::parse(raw_url)? {
+        if template.uses_path() && !is_prefix {
+            return Err(UrlError::TemplateRequiresPrefix);
+        }
+    }
+
+    return Ok(());
+}
+
+fn validate_destination(raw_url: &str) -> Result<(), UrlError> {
     let url = url::Url::parse(raw_url).map_err(|e| UrlError::UrlParseError(e.to_string()))?;
 
     if url.domain().is_none() {
@@ -102,457 +346,1701 @@ fn validate_url(raw_url: &str) -> Result<(), UrlError> {
     return Ok(());
 }
 
-#[rocket::async_trait]
-impl UrlService for DbUrlService {
-    async fn create(&self, user: User, url: CreateUrlRequest) -> Result<Url, UrlError> {
-        validate_key(&url.key)?;
-        validate_url(&url.url)?;
-
-        let key = url.key.to_ascii_lowercase();
+fn validate_window(
+    activates_at: &Option<DateTime<Utc>>,
+    expires_at: &Option<DateTime<Utc>>,
+) -> Result<(), UrlError> {
+    if let (Some(activates_at), Some(expires_at)) = (activates_at, expires_at) {
+        if activates_at >= expires_at {
+            return Err(UrlError::WindowInvalid);
+        }
+    }
 
-        let (key, url, user_id) = self
-            .db
-            .run(move |connection| {
-                for _ in connection
-                    .query("SELECT key FROM key_urls WHERE key = $1;", &[&key])
-                    .unwrap()
-                {
-                    return Err(UrlError::KeyAlreadyExists);
-                }
+    return Ok(());
+}
 
-                let rows = connection
-                    .execute(
-                        "INSERT INTO key_urls (key, url, user_id) VALUES ($1, $2, $3);",
-                        &[&key, &url.url, &user.id],
-                    )
-                    .unwrap();
+fn path_segments(path: &str) -> Vec<String> {
+    return path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(String::from)
+        .collect();
+}
 
-                if rows != 1 {
-                    return Err(UrlError::Unknown);
-                }
+/// Resolves a request path from the redirect cache alone, or `None` on a
+/// miss. Only an exact match is used, a longer key might not be cached.
+pub fn get_cached_by_path(
+    redirect_cache: &RedirectCache,
+    path: &str,
+) -> Option<Result<ResolvedUrl, UrlError>> {
+    let key = path_segments(path).join("/").to_ascii_lowercase();
 
-                for row in connection
-                    .query(
-                        "SELECT key, url, user_id FROM key_urls WHERE key = $1;",
-                        &[&key],
-                    )
-                    .unwrap()
-                {
-                    let value: &str = row.get("key");
-                    let key = String::from(value);
+    let url = redirect_cache.get(&key)?;
 
-                    let value: &str = row.get("url");
-                    let url = String::from(value);
+    return Some(check_window(url).map(|url| ResolvedUrl {
+        url,
+        remainder: vec![],
+    }));
+}
 
-                    let user_id: i32 = row.get("user_id");
+fn check_window(url: Url) -> Result<Url, UrlError> {
+    return match url.window_state() {
+        UrlWindowState::Active => Ok(url),
+        UrlWindowState::Pending => Err(UrlError::NotYetActive),
+        UrlWindowState::Expired => Err(UrlError::Expired),
+    };
+}
 
-                    return Ok((key, url, user_id));
-                }
+fn validate_passphrase(passphrase: &str, limits: &LengthLimits) -> Result<(), UrlError> {
+    let length = passphrase.len();
 
-                return Err(UrlError::Unknown);
-            })
-            .await?;
+    if length < limits.min_length {
+        return Err(UrlError::PassphraseTooShort {
+            min: limits.min_length,
+        });
+    }
 
-        return Ok(Url { key, url, user_id });
+    if length > limits.max_length {
+        return Err(UrlError::PassphraseTooLong {
+            max: limits.max_length,
+        });
     }
 
-    async fn get_all(&self) -> Result<Vec<Url>, UrlError> {
-        return self
-            .db
-            .run(move |connection| {
-                let mut urls = vec![];
+    return Ok(());
+}
 
-                for row in connection
-                    .query(
-                        "SELECT key, url, user_id FROM key_urls ORDER BY key ASC;",
-                        &[],
-                    )
-                    .unwrap()
-                {
-                    let value: &str = row.get("key");
-                    let key = String::from(value);
+fn validate_redirect_type(redirect_type: &Option<u16>) -> Result<(), UrlError> {
+    if let Some(code) = redirect_type {
+        if RedirectType::from_code(*code).is_none() {
+            return Err(UrlError::RedirectTypeInvalid {
+                allowed: RedirectType::CODES.to_vec(),
+            });
+        }
+    }
 
-                    let value: &str = row.get("url");
-                    let url = String::from(value);
+    return Ok(());
+}
 
-                    let user_id: i32 = row.get("user_id");
+/// Redirect types are stored as their status code.
+fn redirect_type_to_sql(redirect_type: &Option<u16>) -> Option<i16> {
+    return redirect_type.map(|code| code as i16);
+}
 
-                    urls.push(Url { key, url, user_id });
-                }
+/// Checks the host of a destination against the domain rules. Templates can't
+/// change the host, so any expansion of them will do.
+fn check_domain_rules(rules: &[DomainRule], raw_url: &str) -> Result<(), UrlError> {
+    let destination = match UrlTemplate::parse(raw_url)? {
+        Some(template) => template.expand_with("a"),
+        None => String::from(raw_url),
+    };
 
-                return Ok(urls);
-            })
-            .await;
-    }
+    let host = url::Url::parse(&destination)
+        .ok()
+        .and_then(|destination| destination.host_str().map(String::from));
 
-    async fn get_all_for_user(&self, user: User) -> Result<Vec<Url>, UrlError> {
-        return self.get_all_by_user_id(user.id).await;
+    if let Some(host) = host {
+        domain::check_domain(rules, &host)?;
     }
 
-    async fn get_all_by_user_id(&self, user_id: i32) -> Result<Vec<Url>, UrlError> {
-        return self
-            .db
-            .run(move |connection| {
-                let mut urls = vec![];
+    return Ok(());
+}
 
-                for row in connection
-                    .query(
-                        "SELECT key, url, user_id FROM key_urls WHERE user_id = $1 ORDER BY key ASC;",
-                        &[&user_id],
-                    )
-                    .unwrap()
-                {
-                    let value: &str = row.get("key");
-                    let key = String::from(value);
+/// Splits a destination pointing back at this service into the path segments
+/// and query that it requests.
+fn self_path(
+    destination: &str,
+    public_base_url: &url::Url,
+) -> Option<(Vec<String>, Option<String>)> {
+    let destination = url::Url::parse(destination).ok()?;
+
+    if destination.host_str() != public_base_url.host_str()
+        || destination.port_or_known_default() != public_base_url.port_or_known_default()
+    {
+        return None;
+    }
 
-                    let value: &str = row.get("url");
-                    let url = String::from(value);
+    let base_path = public_base_url.path().trim_end_matches('/');
+    let path = destination.path().strip_prefix(base_path)?;
 
-                    let user_id: i32 = row.get("user_id");
+    if !path.is_empty() && !path.starts_with('/') {
+        return None;
+    }
 
-                    urls.push(Url { key, url, user_id });
-                }
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned())
+        .collect();
 
-                return Ok(urls);
-            })
-            .await;
+    if segments.is_empty() {
+        return None;
     }
 
-    async fn get_by_key(&self, key: String) -> Result<Url, UrlError> {
-        let key = key.to_ascii_lowercase();
-
-        let (key, url, user_id) = self
-            .db
-            .run(move |connection| {
-                for row in connection
-                    .query(
-                        "SELECT key, url, user_id FROM key_urls WHERE key = $1;",
-                        &[&key],
-                    )
-                    .unwrap()
-                {
-                    let value: &str = row.get("key");
-                    let key = String::from(value);
+    return Some((segments, destination.query().map(String::from)));
+}
 
-                    let value: &str = row.get("url");
-                    let url = String::from(value);
+/// Finds the URL with the longest key matching the path. Keys shorter than the
+/// path only match prefix links.
+fn find_by_path(
+    client: &mut impl GenericClient,
+    segments: &[String],
+) -> Result<Option<ResolvedUrl>, UrlError> {
+    let key = segments.join("/").to_ascii_lowercase();
 
-                    let user_id: i32 = row.get("user_id");
+    let keys: Vec<String> = (1..=segments.len())
+        .map(|length| segments[..length].join("/").to_ascii_lowercase())
+        .collect();
 
-                    return Ok((key, url, user_id));
-                }
+    for row in client.query(
+        "SELECT key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at FROM key_urls WHERE key = ANY($1) AND (key = $2 OR is_prefix) AND deleted_at IS NULL ORDER BY LENGTH(key) DESC LIMIT 1;",
+        &[&keys, &key],
+    )? {
+        let url = row_to_url(&row);
 
-                return Err(UrlError::NotFound);
-            })
-            .await?;
+        let matched = url.key.split('/').count();
+        let remainder = segments.iter().skip(matched).cloned().collect();
 
-        return Ok(Url { key, url, user_id });
+        return Ok(Some(ResolvedUrl { url, remainder }));
     }
 
-    async fn get_by_key_for_user(&self, user: User, key: String) -> Result<Url, UrlError> {
-        let key = key.to_ascii_lowercase();
+    return Ok(None);
+}
 
-        let (key, url, user_id) = self
-            .db
-            .run(move |connection| {
-                for row in connection
-                    .query(
-                        "SELECT key, url, user_id FROM key_urls WHERE key = $1 AND user_id = $2;",
-                        &[&key, &user.id],
-                    )
-                    .unwrap()
-                {
-                    let value: &str = row.get("key");
-                    let key = String::from(value);
+/// Follows the destination of a URL through any keys of this service it
+/// points at, rejecting chains that come back around or never end.
+fn check_redirect_chain(
+    client: &mut impl GenericClient,
+    url: &Url,
+    public_base_url: Option<&url::Url>,
+) -> Result<(), UrlError> {
+    let public_base_url = match public_base_url {
+        Some(public_base_url) => public_base_url,
+        None => return Ok(()),
+    };
+
+    let mut keys = vec![url.key.clone()];
+
+    let mut destination = ResolvedUrl {
+        url: url.clone(),
+        remainder: vec![],
+    }
+    .destination(None)?;
 
-                    let value: &str = row.get("url");
-                    let url = String::from(value);
+    for _ in 0..MAX_REDIRECT_HOPS {
+        let (segments, query) = match self_path(&destination, public_base_url) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
 
-                    let user_id: i32 = row.get("user_id");
+        let resolved = match find_by_path(client, &segments)? {
+            Some(resolved) => resolved,
+            None => return Ok(()),
+        };
 
-                    return Ok((key, url, user_id));
-                }
+        let seen = keys.contains(&resolved.url.key);
+        keys.push(resolved.url.key.clone());
 
-                return Err(UrlError::NotFound);
-            })
-            .await?;
+        if seen {
+            return Err(UrlError::RedirectLoop { keys });
+        }
 
-        return Ok(Url { key, url, user_id });
+        destination = resolved.destination(query.as_deref())?;
     }
 
-    async fn update_by_key(&self, key: String, url: UpdateUrlRequest) -> Result<Url, UrlError> {
-        let mut key = key.to_ascii_lowercase();
+    return Err(UrlError::RedirectChainTooLong {
+        max: MAX_REDIRECT_HOPS,
+    });
+}
 
-        if let Some(key) = &url.key {
-            validate_key(key)?;
+/// Restores the URLs deleted together with their owner. Rules and other links
+/// may have changed while they were deleted, so the ones that would now be
+/// refused stay deleted, and are returned instead.
+pub fn restore_deleted_with_owner(
+    transaction: &mut Transaction,
+    user_id: i32,
+    deleted_at: DateTime<Utc>,
+    public_base_url: Option<&url::Url>,
+) -> Result<Vec<UnrestoredUrl>, UrlError> {
+    let urls: Vec<Url> = transaction
+        .query(
+            "SELECT key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at FROM key_urls WHERE user_id = $1 AND deleted_at = $2 ORDER BY key FOR UPDATE;",
+            &[&user_id, &deleted_at],
+        )?
+        .iter()
+        .map(row_to_url)
+        .collect();
+
+    let rules = domain::load_domain_rules(transaction)?;
+    let mut unrestored = vec![];
+
+    for url in urls {
+        if let Err(error) = check_domain_rules(&rules, &url.url) {
+            unrestored.push(UnrestoredUrl {
+                key: url.key,
+                error,
+            });
+            continue;
         }
 
-        if let Some(url) = &url.url {
-            validate_url(url)?;
+        let mut savepoint = transaction.transaction()?;
+
+        savepoint.execute(
+            "UPDATE key_urls SET deleted_at = NULL WHERE key = $1;",
+            &[&url.key],
+        )?;
+
+        match check_redirect_chain(&mut savepoint, &url, public_base_url) {
+            Ok(()) => savepoint.commit()?,
+            Err(UrlError::Database(e)) => return Err(UrlError::Database(e)),
+            Err(error) => {
+                savepoint.rollback()?;
+                unrestored.push(UnrestoredUrl {
+                    key: url.key,
+                    error,
+                });
+            }
         }
+    }
 
-        let (key, url, user_id) = self
-            .db
-            .run(move |connection| {
-                let mut found = false;
+    return Ok(unrestored);
+}
 
-                for _ in connection
-                    .query("SELECT key FROM key_urls WHERE key = $1;", &[&key])
-                    .unwrap()
-                {
-                    found = true;
-                }
+/// Inserts a URL under a generated key. A concurrent request can take the key
+/// between the check and the insert, so a conflicting insert is rolled back to
+/// a savepoint and tried again with another key.
+fn insert_with_generated_key(
+    transaction: &mut Transaction,
+    key_generator: &KeyGenerator,
+    key_limits: &KeyLimits,
+    insert: impl Fn(&mut Transaction, &str) -> Result<Url, UrlError>,
+) -> Result<Url, UrlError> {
+    for _ in 0..key_generator.max_attempts() {
+        let key = key_generator.generate();
+
+        if validate_key(&key, key_limits).is_err() {
+            continue;
+        }
 
-                if !found {
-                    return Err(UrlError::NotFound);
-                }
+        let rows = transaction.query("SELECT key FROM key_urls WHERE key = $1;", &[&key])?;
 
-                if let Some(url_key) = url.key {
-                    let url_key = url_key.to_ascii_lowercase();
+        if !rows.is_empty() {
+            continue;
+        }
 
-                    if url_key != key {
-                        for _ in connection
-                            .query("SELECT key FROM key_urls WHERE key = $1;", &[&url_key])
-                            .unwrap()
-                        {
-                            return Err(UrlError::KeyAlreadyExists);
-                        }
-                    }
+        let mut savepoint = transaction.transaction()?;
 
-                    let rows = connection
-                        .execute(
-                            "UPDATE key_urls SET key = $1 WHERE key = $2;",
-                            &[&url_key, &key],
-                        )
-                        .unwrap();
+        match insert(&mut savepoint, &key) {
+            Ok(created) => {
+                savepoint.commit()?;
 
-                    if rows != 1 {
-                        return Err(UrlError::Unknown);
-                    }
+                return Ok(created);
+            }
+            Err(UrlError::KeyAlreadyExists) => savepoint.rollback()?,
+            Err(e) => return Err(e),
+        }
+    }
 
-                    key = url_key;
-                }
+    return Err(UrlError::KeyGenerationFailed);
+}
 
-                if let Some(url) = url.url {
-                    let rows = connection
-                        .execute(
-                            "UPDATE key_urls SET url = $1 WHERE key = $2;",
-                            &[&url, &key],
-                        )
-                        .unwrap();
+/// Checks that a key is free to use. A deleted URL keeps its key reserved for
+/// the retention period, after which it is purged to free the key.
+fn claim_key(
+    client: &mut impl GenericClient,
+    key: &str,
+    retention: chrono::Duration,
+) -> Result<(), UrlError> {
+    let cutoff = Utc::now() - retention;
+
+    client.execute(
+        "DELETE FROM key_urls WHERE key = $1 AND deleted_at < $2;",
+        &[&key, &cutoff],
+    )?;
+
+    for row in client.query("SELECT deleted_at FROM key_urls WHERE key = $1;", &[&key])? {
+        let deleted_at: Option<DateTime<Utc>> = row.get("deleted_at");
+
+        return Err(match deleted_at {
+            Some(deleted_at) => UrlError::KeyRecentlyDeleted {
+                available_at: deleted_at + retention,
+            },
+            None => UrlError::KeyAlreadyExists,
+        });
+    }
 
-                    if rows != 1 {
-                        return Err(UrlError::Unknown);
-                    }
-                }
+    return Ok(());
+}
 
-                for row in connection
-                    .query(
-                        "SELECT key, url, user_id FROM key_urls WHERE key = $1;",
-                        &[&key],
-                    )
-                    .unwrap()
-                {
-                    let value: &str = row.get("key");
-                    let key = String::from(value);
+/// Applies an update to the URL with the given key, optionally restricted to
+/// URLs owned by or shared with `user_id`. The row stays locked for the rest of the
+/// transaction, and a key rename is written together with the other changes.
+/// The passphrase is passed already hashed, `Some(None)` removing it.
+/// Returns the URL before and after the update.
+fn update_url(
+    transaction: &mut Transaction,
+    key: String,
+    user_id: Option<i32>,
+    url: UpdateUrlRequest,
+    passphrase_hash: Option<Option<String>>,
+    retention: chrono::Duration,
+) -> Result<(Url, Url), UrlError> {
+    let rows = match user_id {
+        Some(user_id) => transaction.query(
+            "SELECT key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at FROM key_urls WHERE key = $1 AND (user_id = $2 OR EXISTS (SELECT 1 FROM key_url_collaborators WHERE key_url_collaborators.key = key_urls.key AND key_url_collaborators.user_id = $2)) AND deleted_at IS NULL FOR UPDATE;",
+            &[&key, &user_id],
+        )?,
+        None => transaction.query(
+            "SELECT key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at FROM key_urls WHERE key = $1 AND deleted_at IS NULL FOR UPDATE;",
+            &[&key],
+        )?,
+    };
+
+    let current = match rows.first() {
+        Some(row) => row_to_url(row),
+        None => return Err(UrlError::NotFound),
+    };
+    let before = current.clone();
+
+    let activates_at = url.activates_at.unwrap_or(current.activates_at);
+    let expires_at = url.expires_at.unwrap_or(current.expires_at);
+
+    validate_window(&activates_at, &expires_at)?;
+    validate_redirect_type(&url.redirect_type)?;
+
+    let redirect_type = match url.redirect_type {
+        Some(code) => Some(code as i16),
+        None => current
+            .redirect_type
+            .map(|redirect_type| redirect_type.code() as i16),
+    };
+
+    let new_key = match url.key {
+        Some(new_key) => new_key.to_ascii_lowercase(),
+        None => current.key,
+    };
+
+    if new_key != key {
+        claim_key(transaction, &new_key, retention)?;
+    }
 
-                    let value: &str = row.get("url");
-                    let url = String::from(value);
+    let new_url = url.url.unwrap_or(current.url);
+    let is_prefix = url.is_prefix.unwrap_or(current.is_prefix);
+
+    validate_template_prefix(&new_url, is_prefix)?;
+
+    let passphrase_hash = passphrase_hash.unwrap_or(current.passphrase_hash);
+    let preview_enabled = url.preview_enabled.unwrap_or(current.preview_enabled);
+
+    for row in transaction.query(
+        "UPDATE key_urls SET key = $1, url = $2, activates_at = $3, expires_at = $4, redirect_type = $5, is_prefix = $6, passphrase_hash = $7, preview_enabled = $8 WHERE key = $9 RETURNING key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at;",
+        &[
+            &new_key,
+            &new_url,
+            &activates_at,
+            &expires_at,
+            &redirect_type,
+            &is_prefix,
+            &passphrase_hash,
+            &preview_enabled,
+            &key,
+        ],
+    )? {
+        return Ok((before, row_to_url(&row)));
+    }
 
-                    let user_id: i32 = row.get("user_id");
+    return Err(UrlError::Unknown);
+}
 
-                    return Ok((key, url, user_id));
-                }
+fn check_user_exists(client: &mut impl GenericClient, user_id: i32) -> Result<(), UrlError> {
+    let rows = client.query(
+        "SELECT id FROM users WHERE id = $1 AND deleted_at IS NULL FOR SHARE;",
+        &[&user_id],
+    )?;
 
-                return Err(UrlError::Unknown);
-            })
-            .await?;
+    if rows.is_empty() {
+        return Err(UrlError::UserNotFound);
+    }
 
-        return Ok(Url { key, url, user_id });
+    return Ok(());
+}
+
+/// Returns the owner of the URL with the given key, provided the user may
+/// manage who it is shared with. Collaborators may only see them when
+/// `allow_collaborators` is set, and get `NotOwner` otherwise.
+fn find_manageable_owner(
+    client: &mut impl GenericClient,
+    key: &str,
+    user: &User,
+    allow_collaborators: bool,
+) -> Result<i32, UrlError> {
+    let rows = client.query(
+        "SELECT user_id, EXISTS (SELECT 1 FROM key_url_collaborators WHERE key_url_collaborators.key = key_urls.key AND key_url_collaborators.user_id = $2) AS is_collaborator FROM key_urls WHERE key = $1 AND deleted_at IS NULL FOR UPDATE;",
+        &[&key, &user.id],
+    )?;
+
+    let row = rows.first().ok_or(UrlError::NotFound)?;
+
+    let owner_id: i32 = row.get("user_id");
+    let is_collaborator: bool = row.get("is_collaborator");
+
+    if owner_id == user.id || user.is_admin {
+        return Ok(owner_id);
     }
 
-    async fn update_by_key_for_user(
-        &self,
-        user: User,
-        key: String,
-        url: UpdateUrlRequest,
-    ) -> Result<Url, UrlError> {
-        let mut key = key.to_ascii_lowercase();
+    return match (is_collaborator, allow_collaborators) {
+        (true, true) => Ok(owner_id),
+        (true, false) => Err(UrlError::NotOwner),
+        (false, _) => Err(UrlError::NotFound),
+    };
+}
 
-        if let Some(key) = &url.key {
-            validate_key(key)?;
-        }
+fn load_collaborators(
+    client: &mut impl GenericClient,
+    key: &str,
+) -> Result<Vec<UrlCollaborator>, UrlError> {
+    let mut collaborators = vec![];
+
+    for row in client.query(
+        "SELECT key_url_collaborators.user_id, users.client_id, key_url_collaborators.created_at FROM key_url_collaborators INNER JOIN users ON users.id = key_url_collaborators.user_id WHERE key_url_collaborators.key = $1 AND users.deleted_at IS NULL ORDER BY key_url_collaborators.created_at ASC, key_url_collaborators.user_id ASC;",
+        &[&key],
+    )? {
+        let value: &str = row.get("client_id");
+
+        collaborators.push(UrlCollaborator {
+            user_id: row.get("user_id"),
+            client_id: String::from(value),
+            created_at: row.get("created_at"),
+        });
+    }
 
-        if let Some(url) = &url.url {
-            validate_url(url)?;
-        }
+    return Ok(collaborators);
+}
 
-        let (key, url, user_id) = self
-            .db
-            .run(move |connection| {
-                let mut found = false;
+fn row_to_url(row: &Row) -> Url {
+    let value: &str = row.get("key");
+    let key = String::from(value);
 
-                for _ in connection
-                    .query(
-                        "SELECT key FROM key_urls WHERE key = $1 AND user_id = $2;",
-                        &[&key, &user.id],
-                    )
-                    .unwrap()
-                {
-                    found = true;
-                }
+    let value: &str = row.get("url");
+    let url = String::from(value);
 
-                if !found {
-                    return Err(UrlError::NotFound);
-                }
+    let user_id: i32 = row.get("user_id");
 
-                if let Some(url_key) = url.key {
-                    let url_key = url_key.to_ascii_lowercase();
+    let activates_at: Option<DateTime<Utc>> = row.get("activates_at");
+    let expires_at: Option<DateTime<Utc>> = row.get("expires_at");
 
-                    if url_key != key {
-                        for _ in connection
-                            .query("SELECT key FROM key_urls WHERE key = $1;", &[&url_key])
-                            .unwrap()
-                        {
-                            return Err(UrlError::KeyAlreadyExists);
-                        }
-                    }
+    let redirect_type: Option<i16> = row.get("redirect_type");
+    let redirect_type = redirect_type.and_then(|code| RedirectType::from_code(code as u16));
 
-                    let rows = connection
-                        .execute(
-                            "UPDATE key_urls SET key = $1 WHERE key = $2;",
-                            &[&url_key, &key],
-                        )
-                        .unwrap();
+    let is_prefix: bool = row.get("is_prefix");
 
-                    if rows != 1 {
-                        return Err(UrlError::Unknown);
-                    }
+    let passphrase_hash: Option<&str> = row.get("passphrase_hash");
+    let passphrase_hash = passphrase_hash.map(String::from);
 
-                    key = url_key;
-                }
+    let preview_enabled: bool = row.get("preview_enabled");
+    let created_at: DateTime<Utc> = row.get("created_at");
+    let deleted_at: Option<DateTime<Utc>> = row.get("deleted_at");
 
-                if let Some(url) = url.url {
-                    let rows = connection
-                        .execute(
-                            "UPDATE key_urls SET url = $1 WHERE key = $2;",
-                            &[&url, &key],
-                        )
-                        .unwrap();
+    return Url {
+        key,
+        url,
+        user_id,
+        activates_at,
+        expires_at,
+        redirect_type,
+        is_prefix,
+        passphrase_hash,
+        preview_enabled,
+        created_at,
+        deleted_at,
+    };
+}
 
-                    if rows != 1 {
-                        return Err(UrlError::Unknown);
-                    }
-                }
+#[rocket::async_trait]
+impl UrlService for DbUrlService {
+    async fn create(&self, user: User, url: CreateUrlRequest) -> Result<Url, UrlError> {
+        if let Some(key) = &url.key {
+            validate_key(key, &self.key_limits)?;
+        }
 
-                for row in connection
-                    .query(
-                        "SELECT key, url, user_id FROM key_urls WHERE key = $1;",
-                        &[&key],
-                    )
-                    .unwrap()
-                {
-                    let value: &str = row.get("key");
-                    let key = String::from(value);
+        validate_url(&url.url)?;
+        validate_template_prefix(&url.url, url.is_prefix.unwrap_or(false))?;
+        validate_window(&url.activates_at, &url.expires_at)?;
+        validate_redirect_type(&url.redirect_type)?;
 
-                    let value: &str = row.get("url");
-                    let url = String::from(value);
+        let key = url.key.map(|key| key.to_ascii_lowercase());
+        let redirect_type = redirect_type_to_sql(&url.redirect_type);
+        let is_prefix = url.is_prefix.unwrap_or(false);
+        let preview_enabled = url.preview_enabled.unwrap_or(true);
+        let key_generator = KeyGeneratorRef::clone(&self.key_generator);
+        let key_limits = self.key_limits.clone();
 
-                    let user_id: i32 = row.get("user_id");
+        let passphrase_hash = match &url.passphrase {
+            Some(passphrase) => Some(self.hash_passphrase(passphrase)?),
+            None => None,
+        };
 
-                    return Ok((key, url, user_id));
-                }
+        let public_base_url = self.public_base_url.clone();
+        let retention = self.retention;
 
-                return Err(UrlError::Unknown);
-            })
-            .await?;
+        return self
+            .db
+            .run(move |connection| {
+                let mut transaction = connection.transaction()?;
+
+                let rules = domain::load_domain_rules(&mut transaction)?;
+                check_domain_rules(&rules, &url.url)?;
+
+                // A concurrent insert of the same key fails on the unique constraint
+                let insert = |transaction: &mut Transaction, key: &str| -> Result<Url, UrlError> {
+                    let mut created = None;
 
-        return Ok(Url { key, url, user_id });
+                    for row in transaction.query(
+                        "INSERT INTO key_urls (key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at;",
+                        &[&key, &url.url, &user.id, &url.activates_at, &url.expires_at, &redirect_type, &is_prefix, &passphrase_hash, &preview_enabled],
+                    )? {
+                        created = Some(row_to_url(&row));
+                    }
+
+                    return created.ok_or(UrlError::Unknown);
+                };
+
+                let created = match key {
+                    Some(key) => {
+                        claim_key(&mut transaction, &key, retention)?;
+
+                        insert(&mut transaction, &key)?
+                    }
+                    None => insert_with_generated_key(
+                        &mut transaction,
+                        &key_generator,
+                        &key_limits,
+                        insert,
+                    )?,
+                };
+
+                check_redirect_chain(&mut transaction, &created, public_base_url.as_ref())?;
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: user.id,
+                        action: AuditAction::UrlCreate,
+                        target: AuditTarget::Url(created.key.clone()),
+                        before: None,
+                        after: Some(audit::url_snapshot(&created)),
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(created);
+            })
+            .await;
     }
 
-    async fn delete_by_key(&self, key: String) -> Result<(), UrlError> {
+    async fn import(
+        &self,
+        user: User,
+        urls: Vec<Result<ImportUrlRequest, UnreadableImportRow>>,
+        mode: ImportMode,
+    ) -> Result<ImportReport, UrlError> {
+        if urls.len() > MAX_IMPORT_ROWS {
+            return Err(UrlError::ImportTooLarge {
+                max: MAX_IMPORT_ROWS,
+            });
+        }
+
+        let urls: Vec<(String, Result<ImportUrlRequest, UrlError>)> = urls
+            .into_iter()
+            .map(|url| {
+                let mut url = match url {
+                    Ok(url) => url,
+                    Err(unreadable) => {
+                        return (
+                            unreadable.key,
+                            Err(UrlError::ImportInvalid(unreadable.error)),
+                        )
+                    }
+                };
+
+                url.key = url.key.to_ascii_lowercase();
+
+                let valid = validate_key(&url.key, &self.key_limits)
+                    .and_then(|_| validate_url(&url.url))
+                    .and_then(|_| {
+                        validate_template_prefix(&url.url, url.is_prefix.unwrap_or(false))
+                    })
+                    .and_then(|_| validate_window(&url.activates_at, &url.expires_at))
+                    .and_then(|_| validate_redirect_type(&url.redirect_type));
+
+                return (url.key.clone(), valid.map(|_| url));
+            })
+            .collect();
+
+        let public_base_url = self.public_base_url.clone();
+        let retention = self.retention;
+
+        return self
+            .db
+            .run(move |connection| -> Result<ImportReport, UrlError> {
+                let mut transaction = connection.transaction()?;
+
+                let rules = domain::load_domain_rules(&mut transaction)?;
+
+                let mut rows = vec![];
+                let mut failed = false;
+
+                for (index, (key, url)) in urls.into_iter().enumerate() {
+                    let url = url.and_then(|url| {
+                        check_domain_rules(&rules, &url.url)?;
+
+                        return Ok(url);
+                    });
+
+                    let status = match url {
+                        Ok(url) => {
+                            // Each row gets a savepoint so that a duplicate key
+                            // only discards that row
+                            let mut savepoint = transaction.transaction()?;
+
+                            let redirect_type = redirect_type_to_sql(&url.redirect_type);
+                            let is_prefix = url.is_prefix.unwrap_or(false);
+
+                            let inserted = claim_key(&mut savepoint, &url.key, retention).and_then(|_| {
+                                return savepoint.query(
+                                    "INSERT INTO key_urls (key, url, user_id, activates_at, expires_at, redirect_type, is_prefix) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at;",
+                                    &[&url.key, &url.url, &user.id, &url.activates_at, &url.expires_at, &redirect_type, &is_prefix],
+                                ).map_err(UrlError::from);
+                            });
+
+                            match inserted {
+                                Ok(rows) => {
+                                    let created = rows.first().map(row_to_url).ok_or(UrlError::Unknown)?;
+
+                                    // Dropping the savepoint discards a row that loops
+                                    match check_redirect_chain(&mut savepoint, &created, public_base_url.as_ref()) {
+                                        Ok(()) => {
+                                            audit::record(
+                                                &mut savepoint,
+                                                NewAuditEntry {
+                                                    actor_id: user.id,
+                                                    action: AuditAction::UrlCreate,
+                                                    target: AuditTarget::Url(created.key.clone()),
+                                                    before: None,
+                                                    after: Some(audit::url_snapshot(&created)),
+                                                },
+                                            )?;
+
+                                            savepoint.commit()?;
+                                            ImportRowStatus::Created
+                                        }
+                                        Err(e) => ImportRowStatus::Failed(e),
+                                    }
+                                }
+                                Err(UrlError::Database(e)) => return Err(UrlError::Database(e)),
+                                Err(e) => ImportRowStatus::Failed(e),
+                            }
+                        }
+                        Err(e) => ImportRowStatus::Failed(e),
+                    };
+
+                    if let ImportRowStatus::Failed(_) = status {
+                        failed = true;
+                    }
+
+                    rows.push(ImportRowResult {
+                        row: index + 1,
+                        key,
+                        status,
+                    });
+                }
+
+                if failed && mode == ImportMode::AllOrNothing {
+                    transaction.rollback()?;
+
+                    for row in rows.iter_mut() {
+                        if let ImportRowStatus::Created = row.status {
+                            row.status = ImportRowStatus::RolledBack;
+                        }
+                    }
+
+                    return Ok(ImportReport {
+                        committed: false,
+                        rows,
+                    });
+                }
+
+                transaction.commit()?;
+
+                return Ok(ImportReport {
+                    committed: true,
+                    rows,
+                });
+            })
+            .await;
+    }
+
+    async fn get_all(&self, query: UrlQuery) -> Result<Page<Url>, UrlError> {
+        let limit = query.page.limit();
+        let offset = query.page.offset().ok_or(UrlError::CursorInvalid)?;
+
+        let sort = match query.sort {
+            UrlSort::Key => "key",
+            UrlSort::Url => "url",
+            UrlSort::UserId => "user_id",
+            UrlSort::ActivatesAt => "activates_at",
+            UrlSort::ExpiresAt => "expires_at",
+        };
+        let order = query.order.as_sql();
+
+        let mut conditions: Vec<String> = vec![String::from("deleted_at IS NULL")];
+        let mut params: Vec<Box<dyn ToSql + Sync + Send>> = vec![];
+
+        if let Some(key_prefix) = query.key_prefix {
+            let key_prefix = utils::escape_like(&key_prefix.to_ascii_lowercase());
+
+            params.push(Box::new(format!("{key_prefix}%")));
+            conditions.push(format!("key LIKE ${}", params.len()));
+        }
+
+        if let Some(host) = query.host {
+            let host = utils::escape_like(&host);
+
+            params.push(Box::new(format!("%{host}%")));
+            conditions.push(format!(
+                "substring(url from '^[A-Za-z][A-Za-z0-9+.-]*://(?:[^@/?#]*@)?([^:/?#]+)') ILIKE ${}",
+                params.len()
+            ));
+        }
+
+        if let Some(user_id) = query.user_id {
+            params.push(Box::new(user_id));
+            conditions.push(format!("user_id = ${}", params.len()));
+        }
+
+        if let Some(after_key) = query.after_key {
+            params.push(Box::new(after_key));
+            conditions.push(format!("key > ${}", params.len()));
+        }
+
+        let filter = format!(" WHERE {}", conditions.join(" AND "));
+
+        return self
+            .db
+            .run(move |connection| {
+                let params: Vec<&(dyn ToSql + Sync)> = params
+                    .iter()
+                    .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+                    .collect();
+
+                let mut total = 0;
+
+                for row in connection.query(
+                    format!("SELECT COUNT(*) AS total FROM key_urls{filter};").as_str(),
+                    &params,
+                )? {
+                    total = row.get("total");
+                }
+
+                let mut urls = vec![];
+
+                for row in connection.query(
+                    format!(
+                        "SELECT key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at FROM key_urls{filter} ORDER BY {sort} {order} NULLS LAST, key ASC LIMIT {limit} OFFSET {offset};"
+                    )
+                    .as_str(),
+                    &params,
+                )? {
+                    urls.push(row_to_url(&row));
+                }
+
+                return Ok(Page::new(urls, total, offset));
+            })
+            .await;
+    }
+
+    async fn get_all_for_user(&self, user: User, query: UrlQuery) -> Result<Page<Url>, UrlError> {
+        return self.get_all_by_user_id(user.id, query).await;
+    }
+
+    async fn get_all_by_user_id(
+        &self,
+        user_id: i32,
+        query: UrlQuery,
+    ) -> Result<Page<Url>, UrlError> {
+        return self
+            .get_all(UrlQuery {
+                user_id: Some(user_id),
+                ..query
+            })
+            .await;
+    }
+
+    async fn get_by_key(&self, key: String) -> Result<Url, UrlError> {
+        let url = self.get_by_key_in_any_window(key).await?;
+
+        return check_window(url);
+    }
+
+    async fn get_by_key_in_any_window(&self, key: String) -> Result<Url, UrlError> {
         let key = key.to_ascii_lowercase();
 
-        self.db
+        let cached = match &self.redirect_cache {
+            Some(redirect_cache) => redirect_cache.get(&key),
+            None => None,
+        };
+
+        let url = match cached {
+            Some(url) => url,
+            None => {
+                let generation = self.redirect_cache.as_ref().map(|cache| cache.generation());
+
+                let url = self
+                    .db
+                    .run(move |connection| {
+                        for row in connection.query(
+                            "SELECT key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at FROM key_urls WHERE key = $1 AND deleted_at IS NULL;",
+                            &[&key],
+                        )? {
+                            return Ok(row_to_url(&row));
+                        }
+
+                        return Err(UrlError::NotFound);
+                    })
+                    .await?;
+
+                if let (Some(redirect_cache), Some(generation)) = (&self.redirect_cache, generation)
+                {
+                    redirect_cache.insert(url.clone(), generation);
+                }
+
+                url
+            }
+        };
+
+        return Ok(url);
+    }
+
+    async fn verify_passphrase(&self, url: &Url, passphrase: String) -> Result<bool, UrlError> {
+        return match &url.passphrase_hash {
+            Some(hash) => self
+                .password_service
+                .verify_client_secret(hash, &passphrase)
+                .map_err(|e| UrlError::HashError(format!("{:?}", e))),
+            None => Ok(true),
+        };
+    }
+
+    async fn get_by_path(&self, path: String) -> Result<ResolvedUrl, UrlError> {
+        let segments = path_segments(&path);
+
+        let generation = self.redirect_cache.as_ref().map(|cache| cache.generation());
+
+        let resolved = self
+            .db
             .run(move |connection| {
-                let mut found = false;
+                return find_by_path(connection, &segments)?.ok_or(UrlError::NotFound);
+            })
+            .await?;
+
+        if let (Some(redirect_cache), Some(generation)) = (&self.redirect_cache, generation) {
+            redirect_cache.insert(resolved.url.clone(), generation);
+        }
+
+        return Ok(ResolvedUrl {
+            url: check_window(resolved.url)?,
+            remainder: resolved.remainder,
+        });
+    }
+
+    async fn get_by_key_for_user(&self, user: User, key: String) -> Result<Url, UrlError> {
+        let key = key.to_ascii_lowercase();
 
-                for _ in connection
-                    .query("SELECT key FROM key_urls WHERE key = $1;", &[&key])
-                    .unwrap()
+        return self
+            .db
+            .run(move |connection| {
+                for row in connection
+                    .query(
+                        "SELECT key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at FROM key_urls WHERE key = $1 AND (user_id = $2 OR EXISTS (SELECT 1 FROM key_url_collaborators WHERE key_url_collaborators.key = key_urls.key AND key_url_collaborators.user_id = $2)) AND deleted_at IS NULL;",
+                        &[&key, &user.id],
+                    )?
                 {
-                    found = true;
+                    return Ok(row_to_url(&row));
                 }
 
-                if !found {
-                    return Err(UrlError::NotFound);
+                return Err(UrlError::NotFound);
+            })
+            .await;
+    }
+
+    async fn update_by_key(
+        &self,
+        actor: User,
+        key: String,
+        url: UpdateUrlRequest,
+    ) -> Result<Url, UrlError> {
+        let key = key.to_ascii_lowercase();
+        let cache_key = key.clone();
+
+        if let Some(key) = &url.key {
+            validate_key(key, &self.key_limits)?;
+        }
+
+        if let Some(url) = &url.url {
+            validate_url(url)?;
+        }
+
+        let passphrase_hash = self.hash_updated_passphrase(&url.passphrase)?;
+        let public_base_url = self.public_base_url.clone();
+        let retention = self.retention;
+
+        let updated = self
+            .db
+            .run(move |connection| -> Result<Url, UrlError> {
+                let mut transaction = connection.transaction()?;
+
+                if let Some(url) = &url.url {
+                    let rules = domain::load_domain_rules(&mut transaction)?;
+                    check_domain_rules(&rules, url)?;
                 }
 
-                let rows = connection
-                    .execute("DELETE FROM key_urls WHERE key = $1;", &[&key])
-                    .unwrap();
+                let (before, updated) =
+                    update_url(&mut transaction, key, None, url, passphrase_hash, retention)?;
 
-                if rows != 1 {
-                    return Err(UrlError::Unknown);
+                check_redirect_chain(&mut transaction, &updated, public_base_url.as_ref())?;
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: actor.id,
+                        action: AuditAction::UrlUpdate,
+                        target: AuditTarget::Url(updated.key.clone()),
+                        before: Some(audit::url_snapshot(&before)),
+                        after: Some(audit::url_snapshot(&updated)),
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(updated);
+            })
+            .await?;
+
+        self.invalidate_cached(&cache_key);
+        self.invalidate_cached(&updated.key);
+
+        return Ok(updated);
+    }
+
+    async fn update_by_key_for_user(
+        &self,
+        user: User,
+        key: String,
+        url: UpdateUrlRequest,
+    ) -> Result<Url, UrlError> {
+        let key = key.to_ascii_lowercase();
+        let cache_key = key.clone();
+
+        if let Some(key) = &url.key {
+            validate_key(key, &self.key_limits)?;
+        }
+
+        if let Some(url) = &url.url {
+            validate_url(url)?;
+        }
+
+        let passphrase_hash = self.hash_updated_passphrase(&url.passphrase)?;
+        let public_base_url = self.public_base_url.clone();
+        let retention = self.retention;
+
+        let updated = self
+            .db
+            .run(move |connection| -> Result<Url, UrlError> {
+                let mut transaction = connection.transaction()?;
+
+                if let Some(url) = &url.url {
+                    let rules = domain::load_domain_rules(&mut transaction)?;
+                    check_domain_rules(&rules, url)?;
                 }
 
+                let (before, updated) = update_url(
+                    &mut transaction,
+                    key,
+                    Some(user.id),
+                    url,
+                    passphrase_hash,
+                    retention,
+                )?;
+
+                check_redirect_chain(&mut transaction, &updated, public_base_url.as_ref())?;
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: user.id,
+                        action: AuditAction::UrlUpdate,
+                        target: AuditTarget::Url(updated.key.clone()),
+                        before: Some(audit::url_snapshot(&before)),
+                        after: Some(audit::url_snapshot(&updated)),
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(updated);
+            })
+            .await?;
+
+        self.invalidate_cached(&cache_key);
+        self.invalidate_cached(&updated.key);
+
+        return Ok(updated);
+    }
+
+    async fn delete_by_key(&self, actor: User, key: String) -> Result<(), UrlError> {
+        let key = key.to_ascii_lowercase();
+        let cache_key = key.clone();
+
+        self.db
+            .run(move |connection| -> Result<(), UrlError> {
+                let mut transaction = connection.transaction()?;
+
+                let rows = transaction.query(
+                    "UPDATE key_urls SET deleted_at = NOW() WHERE key = $1 AND deleted_at IS NULL RETURNING key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at;",
+                    &[&key],
+                )?;
+
+                let deleted = rows.first().map(row_to_url).ok_or(UrlError::NotFound)?;
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: actor.id,
+                        action: AuditAction::UrlDelete,
+                        target: AuditTarget::Url(deleted.key.clone()),
+                        before: Some(audit::url_snapshot(&deleted)),
+                        after: None,
+                    },
+                )?;
+
+                transaction.commit()?;
+
                 return Ok(());
             })
             .await?;
 
+        self.invalidate_cached(&cache_key);
+
         return Ok(());
     }
 
     async fn delete_by_key_for_user(&self, user: User, key: String) -> Result<(), UrlError> {
         let key = key.to_ascii_lowercase();
+        let cache_key = key.clone();
 
         self.db
+            .run(move |connection| -> Result<(), UrlError> {
+                let mut transaction = connection.transaction()?;
+
+                let rows = transaction.query(
+                    "UPDATE key_urls SET deleted_at = NOW() WHERE key = $1 AND (user_id = $2 OR EXISTS (SELECT 1 FROM key_url_collaborators WHERE key_url_collaborators.key = key_urls.key AND key_url_collaborators.user_id = $2)) AND deleted_at IS NULL RETURNING key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at;",
+                    &[&key, &user.id],
+                )?;
+
+                let deleted = rows.first().map(row_to_url).ok_or(UrlError::NotFound)?;
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: user.id,
+                        action: AuditAction::UrlDelete,
+                        target: AuditTarget::Url(deleted.key.clone()),
+                        before: Some(audit::url_snapshot(&deleted)),
+                        after: None,
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(());
+            })
+            .await?;
+
+        self.invalidate_cached(&cache_key);
+
+        return Ok(());
+    }
+
+    async fn get_all_deleted(&self, page: PageRequest) -> Result<Page<Url>, UrlError> {
+        let limit = page.limit();
+        let offset = page.offset().ok_or(UrlError::CursorInvalid)?;
+
+        return self
+            .db
             .run(move |connection| {
-                let mut found = false;
+                let mut total = 0;
 
-                for _ in connection
-                    .query(
-                        "SELECT key FROM key_urls WHERE key = $1 AND user_id = $2;",
-                        &[&key, &user.id],
+                for row in connection.query(
+                    "SELECT COUNT(*) AS total FROM key_urls WHERE deleted_at IS NOT NULL;",
+                    &[],
+                )? {
+                    total = row.get("total");
+                }
+
+                let mut urls = vec![];
+
+                for row in connection.query(
+                    format!(
+                        "SELECT key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at FROM key_urls WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, key ASC LIMIT {limit} OFFSET {offset};"
                     )
-                    .unwrap()
-                {
-                    found = true;
+                    .as_str(),
+                    &[],
+                )? {
+                    urls.push(row_to_url(&row));
                 }
 
-                if !found {
+                return Ok(Page::new(urls, total, offset));
+            })
+            .await;
+    }
+
+    async fn restore_by_key(&self, actor: User, key: String) -> Result<Url, UrlError> {
+        let key = key.to_ascii_lowercase();
+        let cache_key = key.clone();
+        let public_base_url = self.public_base_url.clone();
+
+        let restored = self
+            .db
+            .run(move |connection| -> Result<Url, UrlError> {
+                let mut transaction = connection.transaction()?;
+
+                let rows = transaction.query(
+                    "SELECT users.deleted_at AS owner_deleted_at FROM key_urls INNER JOIN users ON users.id = key_urls.user_id WHERE key_urls.key = $1 AND key_urls.deleted_at IS NOT NULL FOR UPDATE OF key_urls;",
+                    &[&key],
+                )?;
+
+                let row = rows.first().ok_or(UrlError::NotFound)?;
+                let owner_deleted_at: Option<DateTime<Utc>> = row.get("owner_deleted_at");
+
+                if owner_deleted_at.is_some() {
+                    return Err(UrlError::OwnerDeleted);
+                }
+
+                let rows = transaction.query(
+                    "UPDATE key_urls SET deleted_at = NULL WHERE key = $1 RETURNING key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at;",
+                    &[&key],
+                )?;
+
+                let restored = rows.first().map(row_to_url).ok_or(UrlError::Unknown)?;
+
+                // Rules and other links may have changed while the URL was deleted
+                let rules = domain::load_domain_rules(&mut transaction)?;
+                check_domain_rules(&rules, &restored.url)?;
+                check_redirect_chain(&mut transaction, &restored, public_base_url.as_ref())?;
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: actor.id,
+                        action: AuditAction::UrlRestore,
+                        target: AuditTarget::Url(restored.key.clone()),
+                        before: None,
+                        after: Some(audit::url_snapshot(&restored)),
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(restored);
+            })
+            .await?;
+
+        self.invalidate_cached(&cache_key);
+
+        return Ok(restored);
+    }
+
+    async fn transfer_by_key(
+        &self,
+        actor: User,
+        key: String,
+        user_id: i32,
+    ) -> Result<Url, UrlError> {
+        let key = key.to_ascii_lowercase();
+        let cache_key = key.clone();
+
+        let transferred = self
+            .db
+            .run(move |connection| -> Result<Url, UrlError> {
+                let mut transaction = connection.transaction()?;
+
+                check_user_exists(&mut transaction, user_id)?;
+
+                let rows = transaction.query(
+                    "SELECT key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at FROM key_urls WHERE key = $1 AND deleted_at IS NULL FOR UPDATE;",
+                    &[&key],
+                )?;
+
+                let before = rows.first().map(row_to_url).ok_or(UrlError::NotFound)?;
+
+                let rows = transaction.query(
+                    "UPDATE key_urls SET user_id = $1 WHERE key = $2 RETURNING key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at;",
+                    &[&user_id, &key],
+                )?;
+
+                let transferred = rows.first().map(row_to_url).ok_or(UrlError::Unknown)?;
+
+                // The new owner no longer needs to be a collaborator
+                transaction.execute(
+                    "DELETE FROM key_url_collaborators WHERE key = $1 AND user_id = $2;",
+                    &[&key, &user_id],
+                )?;
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: actor.id,
+                        action: AuditAction::UrlTransfer,
+                        target: AuditTarget::Url(transferred.key.clone()),
+                        before: Some(audit::url_snapshot(&before)),
+                        after: Some(audit::url_snapshot(&transferred)),
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(transferred);
+            })
+            .await?;
+
+        self.invalidate_cached(&cache_key);
+
+        return Ok(transferred);
+    }
+
+    async fn transfer_by_user_id(
+        &self,
+        actor: User,
+        from_user_id: i32,
+        to_user_id: i32,
+    ) -> Result<u64, UrlError> {
+        let transferred = self
+            .db
+            .run(move |connection| -> Result<u64, UrlError> {
+                let mut transaction = connection.transaction()?;
+
+                let rows = transaction.query("SELECT id FROM users WHERE id = $1;", &[&from_user_id])?;
+
+                if rows.is_empty() {
                     return Err(UrlError::NotFound);
                 }
 
-                let rows = connection
-                    .execute(
-                        "DELETE FROM key_urls WHERE key = $1 AND user_id = $2;",
-                        &[&key, &user.id],
-                    )
-                    .unwrap();
+                check_user_exists(&mut transaction, to_user_id)?;
 
-                if rows != 1 {
-                    return Err(UrlError::Unknown);
+                if from_user_id == to_user_id {
+                    return Ok(0);
                 }
 
-                return Ok(());
+                let mut before = vec![];
+
+                for row in transaction.query(
+                    "SELECT key, url, user_id, activates_at, expires_at, redirect_type, is_prefix, passphrase_hash, preview_enabled, created_at, deleted_at FROM key_urls WHERE user_id = $1 AND deleted_at IS NULL ORDER BY key ASC FOR UPDATE;",
+                    &[&from_user_id],
+                )? {
+                    before.push(row_to_url(&row));
+                }
+
+                let keys: Vec<String> = before.iter().map(|url| url.key.clone()).collect();
+
+                transaction.execute(
+                    "UPDATE key_urls SET user_id = $1 WHERE key = ANY($2);",
+                    &[&to_user_id, &keys],
+                )?;
+
+                transaction.execute(
+                    "DELETE FROM key_url_collaborators WHERE key = ANY($1) AND user_id = $2;",
+                    &[&keys, &to_user_id],
+                )?;
+
+                for before in &before {
+                    let after = Url {
+                        user_id: to_user_id,
+                        ..before.clone()
+                    };
+
+                    audit::record(
+                        &mut transaction,
+                        NewAuditEntry {
+                            actor_id: actor.id,
+                            action: AuditAction::UrlTransfer,
+                            target: AuditTarget::Url(before.key.clone()),
+                            before: Some(audit::url_snapshot(before)),
+                            after: Some(audit::url_snapshot(&after)),
+                        },
+                    )?;
+                }
+
+                transaction.commit()?;
+
+                return Ok(before.len() as u64);
             })
             .await?;
 
-        return Ok(());
+        if let Some(redirect_cache) = &self.redirect_cache {
+            redirect_cache.invalidate_by_user_id(from_user_id);
+        }
+
+        return Ok(transferred);
     }
 
-    async fn delete_by_user_id(&self, user_id: i32) -> Result<(), UrlError> {
-        self.db
+    async fn get_collaborators(
+        &self,
+        user: User,
+        key: String,
+    ) -> Result<Vec<UrlCollaborator>, UrlError> {
+        let key = key.to_ascii_lowercase();
+
+        return self
+            .db
             .run(move |connection| {
-                let _ = connection
-                    .execute("DELETE FROM key_urls WHERE user_id = $1;", &[&user_id])
-                    .unwrap();
+                find_manageable_owner(connection, &key, &user, true)?;
+
+                return load_collaborators(connection, &key);
+            })
+            .await;
+    }
+
+    async fn add_collaborator(
+        &self,
+        user: User,
+        key: String,
+        collaborator_id: i32,
+    ) -> Result<Vec<UrlCollaborator>, UrlError> {
+        let key = key.to_ascii_lowercase();
+
+        return self
+            .db
+            .run(move |connection| -> Result<Vec<UrlCollaborator>, UrlError> {
+                let mut transaction = connection.transaction()?;
+
+                let owner_id = find_manageable_owner(&mut transaction, &key, &user, false)?;
+
+                if owner_id == collaborator_id {
+                    return Err(UrlError::CollaboratorIsOwner);
+                }
+
+                check_user_exists(&mut transaction, collaborator_id)?;
+
+                let rows = transaction.execute(
+                    "INSERT INTO key_url_collaborators (key, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING;",
+                    &[&key, &collaborator_id],
+                )?;
+
+                if rows == 1 {
+                    audit::record(
+                        &mut transaction,
+                        NewAuditEntry {
+                            actor_id: user.id,
+                            action: AuditAction::UrlShare,
+                            target: AuditTarget::Url(key.clone()),
+                            before: None,
+                            after: Some(audit::collaborator_snapshot(collaborator_id)),
+                        },
+                    )?;
+                }
+
+                let collaborators = load_collaborators(&mut transaction, &key)?;
+
+                transaction.commit()?;
+
+                return Ok(collaborators);
+            })
+            .await;
+    }
+
+    async fn remove_collaborator(
+        &self,
+        user: User,
+        key: String,
+        collaborator_id: i32,
+    ) -> Result<(), UrlError> {
+        let key = key.to_ascii_lowercase();
+
+        return self
+            .db
+            .run(move |connection| -> Result<(), UrlError> {
+                let mut transaction = connection.transaction()?;
+
+                // Collaborators may remove themselves
+                let allow_collaborators = user.id == collaborator_id;
+
+                find_manageable_owner(&mut transaction, &key, &user, allow_collaborators)?;
+
+                let rows = transaction.execute(
+                    "DELETE FROM key_url_collaborators WHERE key = $1 AND user_id = $2;",
+                    &[&key, &collaborator_id],
+                )?;
+
+                if rows != 1 {
+                    return Err(UrlError::NotFound);
+                }
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: user.id,
+                        action: AuditAction::UrlUnshare,
+                        target: AuditTarget::Url(key.clone()),
+                        before: Some(audit::collaborator_snapshot(collaborator_id)),
+                        after: None,
+                    },
+                )?;
+
+                transaction.commit()?;
 
                 return Ok(());
             })
-            .await?;
+            .await;
+    }
+}
 
-        return Ok(());
+#[cfg(test)]
+mod tests {
+    use rocket::{tokio, Ignite, Rocket};
+
+    use super::{validate_key, DbUrlService, UrlService, UrlSettings};
+    use crate::config::{
+        app::{KeyLimits, LengthLimits},
+        database::DbConnection,
+    };
+    use crate::errors::url::UrlError;
+    use crate::services::{
+        key::{KeyGenerator, KeyGeneratorRef, BASE36_ALPHABET},
+        password::{Argon2Config, Argon2ConfigRef, Argon2PasswordService},
+        types::{
+            url::{CreateUrlRequest, UpdateUrlRequest, Url},
+            user::User,
+        },
+    };
+    use crate::test_db::TestDb;
+
+    const CONCURRENT_REQUESTS: usize = 8;
+
+    async fn url_service(rocket: &Rocket<Ignite>) -> Box<dyn UrlService> {
+        let db = DbConnection::get_one(rocket).await.unwrap();
+        let key_generator = KeyGenerator::new(BASE36_ALPHABET, 6, 10, false).unwrap();
+
+        return DbUrlService::new(
+            db,
+            KeyGeneratorRef::new(key_generator),
+            None,
+            Argon2PasswordService::new(Argon2ConfigRef::new(Argon2Config::default())),
+            UrlSettings {
+                public_base_url: None,
+                retention: chrono::Duration::days(30),
+                key_limits: KeyLimits::default(),
+                passphrase_limits: LengthLimits {
+                    min_length: 4,
+                    max_length: 256,
+                },
+            },
+        );
+    }
+
+    async fn create_user(rocket: &Rocket<Ignite>) -> User {
+        let db = DbConnection::get_one(rocket).await.unwrap();
+
+        return db
+            .run(|connection| {
+                let row = connection
+                    .query_one(
+                        "INSERT INTO users (client_id, client_secret) VALUES ('owner', 'secret') RETURNING id, client_id, is_admin;",
+                        &[],
+                    )
+                    .unwrap();
+
+                return User {
+                    id: row.get("id"),
+                    client_id: row.get("client_id"),
+                    is_admin: row.get("is_admin"),
+                };
+            })
+            .await;
+    }
+
+    fn create_request(key: &str) -> CreateUrlRequest {
+        return CreateUrlRequest {
+            key: Some(String::from(key)),
+            url: String::from("https://example.com"),
+            activates_at: None,
+            expires_at: None,
+            redirect_type: None,
+            is_prefix: None,
+            passphrase: None,
+            preview_enabled: None,
+        };
+    }
+
+    fn rename_request(key: &str) -> UpdateUrlRequest {
+        return UpdateUrlRequest {
+            key: Some(String::from(key)),
+            url: None,
+            activates_at: None,
+            expires_at: None,
+            redirect_type: None,
+            is_prefix: None,
+            passphrase: None,
+            preview_enabled: None,
+        };
+    }
+
+    #[test]
+    fn validate_key_rejects_reserved_prefixes_in_any_case() {
+        let limits = KeyLimits::default();
+
+        for key in ["api/x", "API/x", "Client/x"] {
+            assert!(
+                matches!(
+                    validate_key(key, &limits),
+                    Err(UrlError::KeyReserved { .. })
+                ),
+                "{key}"
+            );
+        }
+
+        assert!(validate_key("api", &limits).is_ok());
+        assert!(validate_key("apix/y", &limits).is_ok());
+    }
+
+    #[test]
+    fn validate_key_rejects_reserved_suffixes_and_keys() {
+        let limits = KeyLimits::default();
+
+        for key in [
+            "a/stats",
+            "a/QR",
+            "a/b/collaborators",
+            "a/transfer",
+            "a/collaborators/7",
+        ] {
+            assert!(
+                matches!(
+                    validate_key(key, &limits),
+                    Err(UrlError::KeySuffixReserved { .. })
+                ),
+                "{key}"
+            );
+        }
+
+        assert!(matches!(
+            validate_key("Export", &limits),
+            Err(UrlError::KeyNameReserved { .. })
+        ));
+
+        assert!(validate_key("stats", &limits).is_ok());
+        assert!(validate_key("a/stats/b", &limits).is_ok());
+        assert!(validate_key("a/collaborators/me", &limits).is_ok());
+    }
+
+    #[test]
+    fn validate_key_enforces_length_limits() {
+        let limits = KeyLimits {
+            min_length: 3,
+            max_length: 5,
+            reserved_prefixes: vec![],
+        };
+
+        assert!(matches!(
+            validate_key("ab", &limits),
+            Err(UrlError::KeyTooShort { min: 3 })
+        ));
+        assert!(matches!(
+            validate_key("abcdef", &limits),
+            Err(UrlError::KeyTooLong { max: 5 })
+        ));
+        assert!(validate_key("abc", &limits).is_ok());
+    }
+
+    fn assert_one_claimed_key(results: Vec<Result<Url, UrlError>>) {
+        let mut claimed = 0;
+
+        for result in results {
+            match result {
+                Ok(url) => {
+                    assert_eq!(url.key, "shared");
+                    claimed += 1;
+                }
+                Err(UrlError::KeyAlreadyExists) => {}
+                Err(e) => panic!("expected KeyAlreadyExists, got {:?}", e),
+            }
+        }
+
+        assert_eq!(claimed, 1);
+    }
+
+    #[rocket::async_test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn concurrent_creates_claim_a_key_once() {
+        let test_db = TestDb::create();
+        let rocket = test_db.rocket().await;
+        let user = create_user(&rocket).await;
+
+        let mut tasks = vec![];
+
+        for _ in 0..CONCURRENT_REQUESTS {
+            let url_service = url_service(&rocket).await;
+            let user = user.clone();
+
+            tasks.push(tokio::spawn(async move {
+                return url_service.create(user, create_request("shared")).await;
+            }));
+        }
+
+        let mut results = vec![];
+
+        for task in tasks {
+            results.push(task.await.unwrap());
+        }
+
+        assert_one_claimed_key(results);
+    }
+
+    #[rocket::async_test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn concurrent_renames_claim_a_key_once() {
+        let test_db = TestDb::create();
+        let rocket = test_db.rocket().await;
+        let user = create_user(&rocket).await;
+
+        for i in 0..CONCURRENT_REQUESTS {
+            let key = format!("link-{i}");
+
+            url_service(&rocket)
+                .await
+                .create(user.clone(), create_request(&key))
+                .await
+                .unwrap();
+        }
+
+        let mut tasks = vec![];
+
+        for i in 0..CONCURRENT_REQUESTS {
+            let url_service = url_service(&rocket).await;
+            let user = user.clone();
+
+            tasks.push(tokio::spawn(async move {
+                return url_service
+                    .update_by_key_for_user(user, format!("link-{i}"), rename_request("shared"))
+                    .await;
+            }));
+        }
+
+        let mut results = vec![];
+
+        for task in tasks {
+            results.push(task.await.unwrap());
+        }
+
+        assert_one_claimed_key(results);
     }
 }
@@ -1,27 +1,50 @@
-use crate::config::database::DbConnection;
-use crate::errors::user::UserError;
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use rocket_sync_db_pools::postgres::types::ToSql;
+
+use crate::config::{app::LengthLimits, database::DbConnection, lockout::CredentialLockouts};
+use crate::errors::{url::UrlError, user::UserError};
 
 use super::{
+    audit,
+    cache::RedirectCacheRef,
     password::PasswordService,
-    types::user::{CreateUserRequest, UpdateUserRequest, User},
-    url::UrlService,
+    types::{
+        audit::{AuditAction, AuditTarget, NewAuditEntry},
+        lockout::UserLockout,
+        page::{Page, PageRequest},
+        user::{
+            CreateUserRequest, DeletedUser, RestoredUser, UpdateUserRequest, User, UserQuery,
+            UserSort,
+        },
+    },
+    url::restore_deleted_with_owner,
 };
 
 #[rocket::async_trait]
 pub trait UserService: Send + Sync {
-    async fn create(&self, user: CreateUserRequest) -> Result<User, UserError>;
+    async fn create(&self, actor: User, user: CreateUserRequest) -> Result<User, UserError>;
 
-    async fn get_all(&self, client_id: Option<String>) -> Result<Vec<User>, UserError>;
+    async fn get_all(&self, query: UserQuery) -> Result<Page<User>, UserError>;
 
+    /// Verifies client credentials. Failures count towards locking out both
+    /// the client_id and the IP they come from.
     async fn verify_and_get(
         &self,
         client_id: String,
         client_secret: String,
+        ip: Option<IpAddr>,
     ) -> Result<User, UserError>;
 
     async fn get_by_id(&self, id: i32) -> Result<User, UserError>;
 
-    async fn update_by_id(&self, id: i32, user: UpdateUserRequest) -> Result<User, UserError>;
+    async fn update_by_id(
+        &self,
+        actor: User,
+        id: i32,
+        user: UpdateUserRequest,
+    ) -> Result<User, UserError>;
 
     async fn update_self_client_secret(
         &self,
@@ -29,58 +52,158 @@ pub trait UserService: Send + Sync {
         client_secret: Option<String>,
     ) -> Result<User, UserError>;
 
-    async fn delete_by_id(&self, id: i32) -> Result<(), UserError>;
+    /// Deletes a user together with their URLs. Both can be restored until
+    /// they are purged.
+    async fn delete_by_id(&self, actor: User, id: i32) -> Result<(), UserError>;
+
+    /// Lists deleted users that haven't been purged yet, most recently
+    /// deleted first.
+    async fn get_all_deleted(&self, page: PageRequest) -> Result<Page<DeletedUser>, UserError>;
+
+    /// Restores a deleted user along with the URLs deleted with them.
+    /// Restores a user and the URLs deleted together with them, except those
+    /// that would now be refused.
+    async fn restore_by_id(&self, actor: User, id: i32) -> Result<RestoredUser, UserError>;
+
+    async fn get_lockout(&self, id: i32) -> Result<UserLockout, UserError>;
+
+    /// Lifts the user's lockout and forgets their failed attempts.
+    async fn clear_lockout(&self, actor: User, id: i32) -> Result<(), UserError>;
 }
 
 pub struct DbUserService {
     db: DbConnection,
-    url_service: Box<dyn UrlService>,
     password_service: Box<dyn PasswordService>,
+    redirect_cache: Option<RedirectCacheRef>,
+    lockouts: CredentialLockouts,
+    /// Address this service is reachable at, used to detect redirect loops
+    public_base_url: Option<url::Url>,
+    client_id_limits: LengthLimits,
+    client_secret_limits: LengthLimits,
 }
 
 impl DbUserService {
     pub fn new(
         db: DbConnection,
-        url_service: Box<dyn UrlService>,
         password_service: Box<dyn PasswordService>,
+        redirect_cache: Option<RedirectCacheRef>,
+        lockouts: CredentialLockouts,
+        public_base_url: Option<url::Url>,
+        client_id_limits: LengthLimits,
+        client_secret_limits: LengthLimits,
     ) -> Box<dyn UserService> {
         return Box::new(Self {
             db,
-            url_service,
             password_service,
+            redirect_cache,
+            lockouts,
+            public_base_url,
+            client_id_limits,
+            client_secret_limits,
         });
     }
-}
 
-fn validate_client_id(client_id: &str) -> Result<(), UserError> {
-    const MIN: usize = 3;
-    const MAX: usize = 256;
+    fn check_lockouts(&self, client_id: &str, ip: Option<&str>) -> Result<(), UserError> {
+        let retry_after = [
+            self.lockouts.clients.retry_after(client_id),
+            ip.and_then(|ip| self.lockouts.ips.retry_after(ip)),
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+
+        return match retry_after {
+            Some(retry_after) => Err(UserError::LockedOut {
+                retry_after: retry_after.as_secs().max(1),
+            }),
+            None => Ok(()),
+        };
+    }
+
+    /// Stores a verified client secret hashed with the current parameters.
+    /// Failing to is only logged, as the secret was verified all the same.
+    async fn rehash_client_secret(&self, id: i32, old_hash: String, client_secret: &str) {
+        let hash = match self.password_service.generate_hash(client_secret) {
+            Ok(hash) => hash,
+            Err(e) => {
+                error!("Failed to rehash the client secret of user {}: {:?}", id, e);
+                return;
+            }
+        };
+
+        // A secret changed in the meantime is left alone
+        let result = self
+            .db
+            .run(move |connection| {
+                return connection.execute(
+                    "UPDATE users SET client_secret = $1 WHERE id = $2 AND client_secret = $3;",
+                    &[&hash, &id, &old_hash],
+                );
+            })
+            .await;
+
+        match result {
+            Ok(1) => info!("Rehashed the client secret of user {}", id),
+            Ok(_) => {}
+            Err(e) => error!(
+                "Failed to store the rehashed client secret of user {}: {}",
+                id, e
+            ),
+        }
+    }
+
+    fn record_failure(&self, client_id: &str, ip: Option<&str>) {
+        if let Some(lockout) = self.lockouts.clients.record_failure(client_id) {
+            warn!(
+                "Locked out client {} for {} seconds after failed verifications",
+                client_id,
+                lockout.as_secs()
+            );
+        }
+
+        if let Some(ip) = ip {
+            if let Some(lockout) = self.lockouts.ips.record_failure(ip) {
+                warn!(
+                    "Locked out IP {} for {} seconds after failed verifications",
+                    ip,
+                    lockout.as_secs()
+                );
+            }
+        }
+    }
+}
 
+fn validate_client_id(client_id: &str, limits: &LengthLimits) -> Result<(), UserError> {
     let length = client_id.len();
 
-    if length < MIN {
-        return Err(UserError::ClientIdTooShort { min: MIN });
+    if length < limits.min_length {
+        return Err(UserError::ClientIdTooShort {
+            min: limits.min_length,
+        });
     }
 
-    if length > MAX {
-        return Err(UserError::ClientIdTooLong { max: MAX });
+    if length > limits.max_length {
+        return Err(UserError::ClientIdTooLong {
+            max: limits.max_length,
+        });
     }
 
     return Ok(());
 }
 
-fn validate_client_secret(client_secret: &str) -> Result<(), UserError> {
-    const MIN: usize = 6;
-    const MAX: usize = 1024;
-
+fn validate_client_secret(client_secret: &str, limits: &LengthLimits) -> Result<(), UserError> {
     let length = client_secret.len();
 
-    if length < MIN {
-        return Err(UserError::ClientSecretTooShort { min: MIN });
+    if length < limits.min_length {
+        return Err(UserError::ClientSecretTooShort {
+            min: limits.min_length,
+        });
     }
 
-    if length > MAX {
-        return Err(UserError::ClientSecretTooLong { max: MAX });
+    if length > limits.max_length {
+        return Err(UserError::ClientSecretTooLong {
+            max: limits.max_length,
+        });
     }
 
     return Ok(());
@@ -88,9 +211,9 @@ fn validate_client_secret(client_secret: &str) -> Result<(), UserError> {
 
 #[rocket::async_trait]
 impl UserService for DbUserService {
-    async fn create(&self, user: CreateUserRequest) -> Result<User, UserError> {
-        validate_client_id(&user.client_id)?;
-        validate_client_secret(&user.client_secret)?;
+    async fn create(&self, actor: User, user: CreateUserRequest) -> Result<User, UserError> {
+        validate_client_id(&user.client_id, &self.client_id_limits)?;
+        validate_client_secret(&user.client_secret, &self.client_secret_limits)?;
 
         let hash = self.password_service.generate_hash(&user.client_secret)?;
 
@@ -100,34 +223,31 @@ impl UserService for DbUserService {
         let (id, client_id, is_admin) = self
             .db
             .run(move |connection| {
-                for _ in connection
-                    .query(
-                        "SELECT client_id FROM users WHERE client_id = $1;",
-                        &[&client_id],
-                    )
-                    .unwrap()
-                {
+                let mut transaction = connection.transaction()?;
+
+                for _ in transaction.query(
+                    "SELECT client_id FROM users WHERE client_id = $1;",
+                    &[&client_id],
+                )? {
                     return Err(UserError::ClientIdAlreadyExists);
                 }
 
-                let rows = connection
-                    .execute(
-                        "INSERT INTO users (client_id, client_secret, is_admin) VALUES ($1, $2, $3);",
-                        &[&client_id, &hash, &is_admin],
-                    )
-                    .unwrap();
+                // A concurrent insert of the same client_id fails on the unique constraint
+                let rows = transaction.execute(
+                    "INSERT INTO users (client_id, client_secret, is_admin) VALUES ($1, $2, $3);",
+                    &[&client_id, &hash, &is_admin],
+                )?;
 
                 if rows != 1 {
                     return Err(UserError::Unknown);
                 }
 
-                for row in connection
-                    .query(
-                        "SELECT id, client_id, is_admin FROM users WHERE client_id = $1;",
-                        &[&client_id],
-                    )
-                    .unwrap()
-                {
+                let mut created = None;
+
+                for row in transaction.query(
+                    "SELECT id, client_id, is_admin FROM users WHERE client_id = $1;",
+                    &[&client_id],
+                )? {
                     let id: i32 = row.get("id");
 
                     let value: &str = row.get("client_id");
@@ -135,10 +255,32 @@ impl UserService for DbUserService {
 
                     let is_admin: bool = row.get("is_admin");
 
-                    return Ok((id, client_id, is_admin));
+                    created = Some((id, client_id, is_admin));
                 }
 
-                return Err(UserError::Unknown);
+                let created = created.ok_or(UserError::Unknown)?;
+
+                let (id, client_id, is_admin) = &created;
+                let snapshot = User {
+                    id: *id,
+                    client_id: client_id.clone(),
+                    is_admin: *is_admin,
+                };
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: actor.id,
+                        action: AuditAction::UserCreate,
+                        target: AuditTarget::User(snapshot.id),
+                        before: None,
+                        after: Some(audit::user_snapshot(&snapshot, true)),
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(created);
             })
             .await?;
 
@@ -149,33 +291,52 @@ impl UserService for DbUserService {
         });
     }
 
-    async fn get_all(&self, client_id: Option<String>) -> Result<Vec<User>, UserError> {
+    async fn get_all(&self, query: UserQuery) -> Result<Page<User>, UserError> {
+        let limit = query.page.limit();
+        let offset = query.page.offset().ok_or(UserError::CursorInvalid)?;
+
+        let sort = match query.sort {
+            UserSort::Id => "id",
+            UserSort::ClientId => "client_id",
+        };
+        let order = query.order.as_sql();
+
+        let mut conditions: Vec<String> = vec![String::from("deleted_at IS NULL")];
+        let mut params: Vec<Box<dyn ToSql + Sync + Send>> = vec![];
+
+        if let Some(client_id) = query.client_id {
+            params.push(Box::new(client_id.to_ascii_lowercase()));
+            conditions.push(format!("client_id LIKE ${}", params.len()));
+        }
+
+        let filter = format!(" WHERE {}", conditions.join(" AND "));
+
         return self
             .db
             .run(move |connection| {
-                let mut users = vec![];
-
-                let rows = match client_id {
-                    Some(client_id) => {
-                        let client_id = client_id.to_ascii_lowercase();
+                let params: Vec<&(dyn ToSql + Sync)> = params
+                    .iter()
+                    .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+                    .collect();
+
+                let mut total = 0;
+
+                for row in connection.query(
+                    format!("SELECT COUNT(*) AS total FROM users{filter};").as_str(),
+                    &params,
+                )? {
+                    total = row.get("total");
+                }
 
-                        connection
-                            .query(
-                                "SELECT id, client_id, client_secret, is_admin FROM users WHERE client_id LIKE $1 ORDER BY id ASC;",
-                                &[&client_id],
-                            )
-                            .unwrap()
-                    }
-                    None => connection
-                        .query(
-                            "SELECT id, client_id, client_secret, is_admin FROM users ORDER BY id ASC;",
-                            &[],
-                        )
-                        .unwrap()
-                };
+                let mut users = vec![];
 
-                for row in rows
-                {
+                for row in connection.query(
+                    format!(
+                        "SELECT id, client_id, is_admin FROM users{filter} ORDER BY {sort} {order}, id ASC LIMIT {limit} OFFSET {offset};"
+                    )
+                    .as_str(),
+                    &params,
+                )? {
                     let id: i32 = row.get("id");
 
                     let value: &str = row.get("client_id");
@@ -190,7 +351,7 @@ impl UserService for DbUserService {
                     });
                 }
 
-                return Ok(users);
+                return Ok(Page::new(users, total, offset));
             })
             .await;
     }
@@ -199,18 +360,23 @@ impl UserService for DbUserService {
         &self,
         client_id: String,
         client_secret: String,
+        ip: Option<IpAddr>,
     ) -> Result<User, UserError> {
         let client_id = client_id.to_ascii_lowercase();
+        let ip = ip.map(|ip| ip.to_string());
+
+        self.check_lockouts(&client_id, ip.as_deref())?;
+
+        let attempted_client_id = client_id.clone();
 
-        let (id, client_id, hash, is_admin) = self
+        let result = self
             .db
             .run(move |connection| {
                 for row in connection
                     .query(
-                        "SELECT id, client_id, client_secret, is_admin FROM users WHERE client_id = $1;",
+                        "SELECT id, client_id, client_secret, is_admin FROM users WHERE client_id = $1 AND deleted_at IS NULL;",
                         &[&client_id],
-                    )
-                    .unwrap()
+                    )?
                 {
                     let id: i32 = row.get("id");
 
@@ -227,16 +393,35 @@ impl UserService for DbUserService {
 
                 return Err(UserError::NotFound);
             })
-            .await?;
+            .await;
+
+        let (id, client_id, hash, is_admin) = match result {
+            Ok(found) => found,
+            Err(UserError::NotFound) => {
+                self.record_failure(&attempted_client_id, ip.as_deref());
+                return Err(UserError::NotFound);
+            }
+            Err(e) => return Err(e),
+        };
 
         let is_valid = self
             .password_service
             .verify_client_secret(&hash, &client_secret)?;
 
         if !is_valid {
+            self.record_failure(&client_id, ip.as_deref());
             return Err(UserError::Invalid);
         }
 
+        // Only the client's failures are forgiven. The IP keeps its failures,
+        // or an attacker could reset them by signing in to an account of
+        // their own between guesses at others.
+        self.lockouts.clients.clear(&client_id);
+
+        if self.password_service.needs_rehash(&hash) {
+            self.rehash_client_secret(id, hash, &client_secret).await;
+        }
+
         return Ok(User {
             id,
             client_id,
@@ -248,13 +433,10 @@ impl UserService for DbUserService {
         let (id, client_id, is_admin) = self
             .db
             .run(move |connection| {
-                for row in connection
-                    .query(
-                        "SELECT id, client_id, is_admin FROM users WHERE id = $1;",
-                        &[&id],
-                    )
-                    .unwrap()
-                {
+                for row in connection.query(
+                    "SELECT id, client_id, is_admin FROM users WHERE id = $1 AND deleted_at IS NULL;",
+                    &[&id],
+                )? {
                     let id: i32 = row.get("id");
 
                     let value: &str = row.get("client_id");
@@ -276,13 +458,18 @@ impl UserService for DbUserService {
         });
     }
 
-    async fn update_by_id(&self, id: i32, user: UpdateUserRequest) -> Result<User, UserError> {
+    async fn update_by_id(
+        &self,
+        actor: User,
+        id: i32,
+        user: UpdateUserRequest,
+    ) -> Result<User, UserError> {
         if let Some(client_id) = &user.client_id {
-            validate_client_id(client_id)?;
+            validate_client_id(client_id, &self.client_id_limits)?;
         }
 
         if let Some(client_secret) = &user.client_secret {
-            validate_client_secret(client_secret)?;
+            validate_client_secret(client_secret, &self.client_secret_limits)?;
         }
 
         let hash = match user.client_secret {
@@ -293,29 +480,33 @@ impl UserService for DbUserService {
         let (id, client_id, is_admin) = self
             .db
             .run(move |connection| {
-                let mut found = false;
+                let mut transaction = connection.transaction()?;
 
-                for _ in connection
-                    .query("SELECT id FROM users WHERE id = $1;", &[&id])
-                    .unwrap()
-                {
-                    found = true;
-                }
+                let mut before = None;
 
-                if !found {
-                    return Err(UserError::NotFound);
+                for row in transaction.query(
+                    "SELECT id, client_id, is_admin FROM users WHERE id = $1 AND deleted_at IS NULL FOR UPDATE;",
+                    &[&id],
+                )? {
+                    let value: &str = row.get("client_id");
+
+                    before = Some(User {
+                        id: row.get("id"),
+                        client_id: String::from(value),
+                        is_admin: row.get("is_admin"),
+                    });
                 }
 
+                let before = before.ok_or(UserError::NotFound)?;
+                let secret_changed = hash.is_some();
+
                 if let Some(client_id) = user.client_id {
                     let client_id = client_id.to_ascii_lowercase();
 
-                    for row in connection
-                        .query(
-                            "SELECT id, client_id FROM users WHERE client_id = $1;",
-                            &[&client_id],
-                        )
-                        .unwrap()
-                    {
+                    for row in transaction.query(
+                        "SELECT id, client_id FROM users WHERE client_id = $1;",
+                        &[&client_id],
+                    )? {
                         let row_id: i32 = row.get("id");
 
                         if row_id != id {
@@ -323,12 +514,10 @@ impl UserService for DbUserService {
                         }
                     }
 
-                    let rows = connection
-                        .execute(
-                            "UPDATE users SET client_id = $1 WHERE id = $2;",
-                            &[&client_id, &id],
-                        )
-                        .unwrap();
+                    let rows = transaction.execute(
+                        "UPDATE users SET client_id = $1 WHERE id = $2;",
+                        &[&client_id, &id],
+                    )?;
 
                     if rows != 1 {
                         return Err(UserError::Unknown);
@@ -336,12 +525,10 @@ impl UserService for DbUserService {
                 }
 
                 if let Some(hash) = hash {
-                    let rows = connection
-                        .execute(
-                            "UPDATE users SET client_secret = $1 WHERE id = $2;",
-                            &[&hash, &id],
-                        )
-                        .unwrap();
+                    let rows = transaction.execute(
+                        "UPDATE users SET client_secret = $1 WHERE id = $2;",
+                        &[&hash, &id],
+                    )?;
 
                     if rows != 1 {
                         return Err(UserError::Unknown);
@@ -349,25 +536,22 @@ impl UserService for DbUserService {
                 }
 
                 if let Some(is_admin) = user.is_admin {
-                    let rows = connection
-                        .execute(
-                            "UPDATE users SET is_admin = $1 WHERE id = $2;",
-                            &[&is_admin, &id],
-                        )
-                        .unwrap();
+                    let rows = transaction.execute(
+                        "UPDATE users SET is_admin = $1 WHERE id = $2;",
+                        &[&is_admin, &id],
+                    )?;
 
                     if rows != 1 {
                         return Err(UserError::Unknown);
                     }
                 }
 
-                for row in connection
-                    .query(
-                        "SELECT id, client_id, is_admin FROM users WHERE id = $1;",
-                        &[&id],
-                    )
-                    .unwrap()
-                {
+                let mut updated = None;
+
+                for row in transaction.query(
+                    "SELECT id, client_id, is_admin FROM users WHERE id = $1;",
+                    &[&id],
+                )? {
                     let id: i32 = row.get("id");
 
                     let value: &str = row.get("client_id");
@@ -375,10 +559,32 @@ impl UserService for DbUserService {
 
                     let is_admin: bool = row.get("is_admin");
 
-                    return Ok((id, client_id, is_admin));
+                    updated = Some((id, client_id, is_admin));
                 }
 
-                return Err(UserError::Unknown);
+                let updated = updated.ok_or(UserError::Unknown)?;
+
+                let (id, client_id, is_admin) = &updated;
+                let after = User {
+                    id: *id,
+                    client_id: client_id.clone(),
+                    is_admin: *is_admin,
+                };
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: actor.id,
+                        action: AuditAction::UserUpdate,
+                        target: AuditTarget::User(after.id),
+                        before: Some(audit::user_snapshot(&before, false)),
+                        after: Some(audit::user_snapshot(&after, secret_changed)),
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(updated);
             })
             .await?;
 
@@ -401,31 +607,30 @@ impl UserService for DbUserService {
             Some(client_secret) => client_secret,
         };
 
-        validate_client_secret(&client_secret)?;
+        validate_client_secret(&client_secret, &self.client_secret_limits)?;
 
         let hash = self.password_service.generate_hash(&client_secret)?;
 
         let (id, client_id, is_admin) = self
             .db
             .run(move |connection| {
-                let rows = connection
-                    .execute(
-                        "UPDATE users SET client_secret = $1 WHERE id = $2;",
-                        &[&hash, &user.id],
-                    )
-                    .unwrap();
+                let mut transaction = connection.transaction()?;
+
+                let rows = transaction.execute(
+                    "UPDATE users SET client_secret = $1 WHERE id = $2;",
+                    &[&hash, &user.id],
+                )?;
 
                 if rows != 1 {
                     return Err(UserError::Unknown);
                 }
 
-                for row in connection
-                    .query(
-                        "SELECT id, client_id, is_admin FROM users WHERE id = $1;",
-                        &[&user.id],
-                    )
-                    .unwrap()
-                {
+                let mut updated = None;
+
+                for row in transaction.query(
+                    "SELECT id, client_id, is_admin FROM users WHERE id = $1;",
+                    &[&user.id],
+                )? {
                     let id: i32 = row.get("id");
 
                     let value: &str = row.get("client_id");
@@ -433,10 +638,25 @@ impl UserService for DbUserService {
 
                     let is_admin: bool = row.get("is_admin");
 
-                    return Ok((id, client_id, is_admin));
+                    updated = Some((id, client_id, is_admin));
                 }
 
-                return Err(UserError::Unknown);
+                let updated = updated.ok_or(UserError::Unknown)?;
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: user.id,
+                        action: AuditAction::UserUpdate,
+                        target: AuditTarget::User(user.id),
+                        before: Some(audit::user_snapshot(&user, false)),
+                        after: Some(audit::user_snapshot(&user, true)),
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(updated);
             })
             .await?;
 
@@ -447,39 +667,232 @@ impl UserService for DbUserService {
         });
     }
 
-    async fn delete_by_id(&self, id: i32) -> Result<(), UserError> {
-        self.url_service
-            .delete_by_user_id(id)
-            .await
-            .map_err(|e| UserError::UrlDeletionError(e))?;
-
+    async fn delete_by_id(&self, actor: User, id: i32) -> Result<(), UserError> {
         self.db
             .run(move |connection| {
-                let mut found = false;
+                let mut transaction = connection.transaction()?;
 
-                for _ in connection
-                    .query("SELECT id FROM users WHERE id = $1;", &[&id])
-                    .unwrap()
-                {
-                    found = true;
+                let mut before = None;
+
+                for row in transaction.query(
+                    "SELECT id, client_id, is_admin FROM users WHERE id = $1 AND deleted_at IS NULL FOR UPDATE;",
+                    &[&id],
+                )? {
+                    let value: &str = row.get("client_id");
+
+                    before = Some(User {
+                        id: row.get("id"),
+                        client_id: String::from(value),
+                        is_admin: row.get("is_admin"),
+                    });
+                }
+
+                let before = before.ok_or(UserError::NotFound)?;
+
+                // The user's URLs share the user's deletion time, so that
+                // restoring the user brings back exactly these URLs
+                let deleted_at = Utc::now();
+
+                transaction.execute(
+                    "UPDATE key_urls SET deleted_at = $1 WHERE user_id = $2 AND deleted_at IS NULL;",
+                    &[&deleted_at, &id],
+                )?;
+
+                let rows = transaction.execute(
+                    "UPDATE users SET deleted_at = $1 WHERE id = $2;",
+                    &[&deleted_at, &id],
+                )?;
+
+                if rows != 1 {
+                    return Err(UserError::Unknown);
+                }
+
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: actor.id,
+                        action: AuditAction::UserDelete,
+                        target: AuditTarget::User(id),
+                        before: Some(audit::user_snapshot(&before, false)),
+                        after: None,
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(());
+            })
+            .await?;
+
+        if let Some(redirect_cache) = &self.redirect_cache {
+            redirect_cache.invalidate_by_user_id(id);
+        }
+
+        return Ok(());
+    }
+
+    async fn get_all_deleted(&self, page: PageRequest) -> Result<Page<DeletedUser>, UserError> {
+        let limit = page.limit();
+        let offset = page.offset().ok_or(UserError::CursorInvalid)?;
+
+        return self
+            .db
+            .run(move |connection| {
+                let mut total = 0;
+
+                for row in connection.query(
+                    "SELECT COUNT(*) AS total FROM users WHERE deleted_at IS NOT NULL;",
+                    &[],
+                )? {
+                    total = row.get("total");
                 }
 
-                if !found {
-                    return Err(UserError::NotFound);
+                let mut users = vec![];
+
+                for row in connection.query(
+                    format!(
+                        "SELECT id, client_id, is_admin, deleted_at FROM users WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC, id ASC LIMIT {limit} OFFSET {offset};"
+                    )
+                    .as_str(),
+                    &[],
+                )? {
+                    let value: &str = row.get("client_id");
+
+                    users.push(DeletedUser {
+                        user: User {
+                            id: row.get("id"),
+                            client_id: String::from(value),
+                            is_admin: row.get("is_admin"),
+                        },
+                        deleted_at: row.get("deleted_at"),
+                    });
+                }
+
+                return Ok(Page::new(users, total, offset));
+            })
+            .await;
+    }
+
+    async fn restore_by_id(&self, actor: User, id: i32) -> Result<RestoredUser, UserError> {
+        let public_base_url = self.public_base_url.clone();
+
+        return self
+            .db
+            .run(move |connection| {
+                let mut transaction = connection.transaction()?;
+
+                let mut deleted = None;
+
+                for row in transaction.query(
+                    "SELECT id, client_id, is_admin, deleted_at FROM users WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE;",
+                    &[&id],
+                )? {
+                    let value: &str = row.get("client_id");
+                    let deleted_at: DateTime<Utc> = row.get("deleted_at");
+
+                    deleted = Some((
+                        User {
+                            id: row.get("id"),
+                            client_id: String::from(value),
+                            is_admin: row.get("is_admin"),
+                        },
+                        deleted_at,
+                    ));
                 }
 
-                let rows = connection
-                    .execute("DELETE FROM users WHERE id = $1;", &[&id])
-                    .unwrap();
+                let (restored, deleted_at) = deleted.ok_or(UserError::NotFound)?;
+
+                // URLs deleted before the user stay deleted
+                let unrestored_urls = restore_deleted_with_owner(
+                    &mut transaction,
+                    id,
+                    deleted_at,
+                    public_base_url.as_ref(),
+                )
+                .map_err(|e| match e {
+                    UrlError::Database(e) => UserError::Database(e),
+                    _ => UserError::Unknown,
+                })?;
+
+                let rows = transaction.execute(
+                    "UPDATE users SET deleted_at = NULL WHERE id = $1;",
+                    &[&id],
+                )?;
 
                 if rows != 1 {
                     return Err(UserError::Unknown);
                 }
 
+                audit::record(
+                    &mut transaction,
+                    NewAuditEntry {
+                        actor_id: actor.id,
+                        action: AuditAction::UserRestore,
+                        target: AuditTarget::User(id),
+                        before: None,
+                        after: Some(audit::user_snapshot(&restored, false)),
+                    },
+                )?;
+
+                transaction.commit()?;
+
+                return Ok(RestoredUser {
+                    user: restored,
+                    unrestored_urls,
+                });
+            })
+            .await;
+    }
+
+    async fn get_lockout(&self, id: i32) -> Result<UserLockout, UserError> {
+        let user = self.get_by_id(id).await?;
+
+        let status = self.lockouts.clients.status(&user.client_id);
+
+        return Ok(UserLockout {
+            user_id: user.id,
+            failures: status.as_ref().map_or(0, |status| status.failures),
+            lockouts: status.as_ref().map_or(0, |status| status.lockouts),
+            locked_until: status.and_then(|status| status.locked_until),
+            client_id: user.client_id,
+        });
+    }
+
+    async fn clear_lockout(&self, actor: User, id: i32) -> Result<(), UserError> {
+        let user = self.get_by_id(id).await?;
+
+        let status = match self.lockouts.clients.status(&user.client_id) {
+            Some(status) => status,
+            None => return Ok(()),
+        };
+
+        let before = audit::lockout_snapshot(&status);
+        let actor_id = actor.id;
+
+        self.db
+            .run(move |connection| -> Result<(), UserError> {
+                audit::record(
+                    connection,
+                    NewAuditEntry {
+                        actor_id,
+                        action: AuditAction::UserUnlock,
+                        target: AuditTarget::User(id),
+                        before: Some(before),
+                        after: None,
+                    },
+                )?;
+
                 return Ok(());
             })
             .await?;
 
+        self.lockouts.clients.clear(&user.client_id);
+
+        info!(
+            "Cleared lockout of client {} on behalf of {}",
+            user.client_id, actor.client_id
+        );
+
         return Ok(());
     }
 }
@@ -0,0 +1,88 @@
+use rocket::{figment::Figment, Config, Ignite, Rocket};
+use rocket_sync_db_pools::postgres::{Client, NoTls};
+
+use crate::config::database::DbConnection;
+use crate::migrations;
+use crate::utils;
+
+/// Scratch database with every migration applied, dropped again when the test
+/// ends. Tests that need one are ignored by default, and are run with
+/// `cargo test -- --ignored` once `TEST_DATABASE_URL` points at a server they
+/// may create databases on.
+pub struct TestDb {
+    admin_url: String,
+    name: String,
+    pub url: String,
+}
+
+impl TestDb {
+    pub fn create() -> TestDb {
+        let admin_url = utils::optional_env_var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must be set to run database tests");
+
+        let name = format!("url_linker_test_{}", uuid::Uuid::new_v4().to_simple());
+
+        let mut url = url::Url::parse(&admin_url).expect("TEST_DATABASE_URL must be a valid URL");
+        url.set_path(&name);
+
+        let test_db = TestDb {
+            admin_url,
+            name,
+            url: String::from(url.as_str()),
+        };
+
+        let (admin_url, name, url) = (
+            test_db.admin_url.clone(),
+            test_db.name.clone(),
+            test_db.url.clone(),
+        );
+
+        // The blocking client can't run inside an async test's runtime
+        std::thread::spawn(move || {
+            let mut admin = Client::connect(&admin_url, NoTls).unwrap();
+            admin
+                .batch_execute(&format!("CREATE DATABASE {name};"))
+                .unwrap();
+
+            let mut client = Client::connect(&url, NoTls).unwrap();
+            migrations::up(&mut client).unwrap();
+        })
+        .join()
+        .unwrap();
+
+        return test_db;
+    }
+
+    pub fn client(&self) -> Client {
+        return Client::connect(&self.url, NoTls).unwrap();
+    }
+
+    /// Rocket instance with a connection pool for this database, which
+    /// `DbConnection::get_one` takes connections from.
+    pub async fn rocket(&self) -> Rocket<Ignite> {
+        let figment = Figment::from(Config::debug_default())
+            .merge(("databases.url_linker.url", &self.url))
+            .merge(("databases.url_linker.pool_size", 16));
+
+        return rocket::custom(figment)
+            .attach(DbConnection::fairing())
+            .ignite()
+            .await
+            .unwrap();
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let admin_url = self.admin_url.clone();
+        let name = self.name.clone();
+
+        let _ = std::thread::spawn(move || {
+            if let Ok(mut admin) = Client::connect(&admin_url, NoTls) {
+                let _ =
+                    admin.batch_execute(&format!("DROP DATABASE IF EXISTS {name} WITH (FORCE);"));
+            }
+        })
+        .join();
+    }
+}
@@ -1,4 +1,11 @@
-pub fn required_env_var(name: &str) -> String {
-    return std::env::var(name)
-        .expect(format!("Required environment variable not found: {name}").as_str());
+pub fn optional_env_var(name: &str) -> Option<String> {
+    return std::env::var(name).ok();
+}
+
+/// Escapes `%`, `_` and `\` so that `value` is matched literally by `LIKE`.
+pub fn escape_like(value: &str) -> String {
+    return value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
 }